@@ -1,4 +1,4 @@
-use std::{borrow::Cow, path::PathBuf};
+use std::{borrow::Cow, fmt, iter::Peekable, path::PathBuf, str::Chars};
 
 // *** Identifiers ***
 
@@ -13,6 +13,7 @@ impl AsRef<str> for Ident<'_> {
     }
 }
 
+#[allow(clippy::should_implement_trait)]
 impl<'input> Ident<'input> {
     pub fn from_str(ident: &'input str) -> Self {
         Self {
@@ -39,97 +40,241 @@ pub struct BoolLit(pub bool);
 
 // *** String literal ***
 
+// Decodes the escapes of a string or char literal (without its quotes), which the lexer has
+// already checked
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+
+        let escaped = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('x') => code_point(&mut chars, 16, 2),
+            Some('u') => code_point(&mut chars, 16, 6),
+            Some('o') => code_point(&mut chars, 8, 3),
+            Some('b') => code_point(&mut chars, 2, 8),
+            // Backslashes, quotes and braces
+            Some(char) => char,
+            None => break,
+        };
+        unescaped.push(escaped);
+    }
+
+    unescaped
+}
+
+// The digits of an escape (as many as there are, up to the max). Values that aren't chars (the
+// lexer only checks the digits) are replaced
+fn code_point(chars: &mut Peekable<Chars<'_>>, radix: u32, max_digits: usize) -> char {
+    let mut value: u32 = 0;
+
+    for _ in 0..max_digits {
+        match chars.peek().and_then(|char| char.to_digit(radix)) {
+            Some(digit) => {
+                value = value * radix + digit;
+                chars.next();
+            }
+            None => break,
+        }
+    }
+
+    char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+// Without its quotes
+fn unquote(s: &str) -> &str {
+    &s[1..s.len() - 1]
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct StringLit<'input> {
     pub unparsed: Cow<'input, str>,
-    parsed: Option<Cow<'input, str>>,
+    parsed: Cow<'input, str>,
 }
 
 impl<'input> StringLit<'input> {
     pub fn from_str(s: &'input str, has_escapes: bool) -> Self {
-        let unparsed = Cow::Borrowed(s);
-
         // if no escapes, then we are already parsed
         let parsed = if has_escapes {
-            None
+            Cow::Owned(unescape(unquote(s)))
         } else {
-            Some(unparsed.clone())
+            Cow::Borrowed(unquote(s))
         };
 
-        Self { unparsed, parsed }
+        Self {
+            unparsed: Cow::Borrowed(s),
+            parsed,
+        }
     }
 
     pub fn from_string(s: String, has_escapes: bool) -> Self {
-        let unparsed: Cow<'_, str> = Cow::Owned(s);
-
         // if no escapes, then we are already parsed
         let parsed = if has_escapes {
-            None
+            unescape(unquote(&s))
         } else {
-            Some(unparsed.clone())
+            unquote(&s).to_string()
         };
 
-        Self { unparsed, parsed }
+        Self {
+            unparsed: Cow::Owned(s),
+            parsed: Cow::Owned(parsed),
+        }
+    }
+
+    // '``"abc"``' - the text has no escapes, so it is already parsed
+    pub fn from_raw(s: &'input str) -> Self {
+        Self {
+            unparsed: Cow::Borrowed(s),
+            parsed: Cow::Borrowed(&s[3..s.len() - 3]),
+        }
+    }
+
+    // A piece of text of an interpolated string (without its quotes or braces), quoted like a
+    // string of its own
+    pub fn from_piece(s: &str, has_escapes: bool) -> Self {
+        Self::from_string(format!("\"{s}\""), has_escapes)
+    }
+
+    // The text, with its escapes decoded
+    pub fn value(&self) -> &str {
+        &self.parsed
+    }
+
+    // The text of a raw string
+    pub fn raw(&self) -> Option<&str> {
+        self.unparsed.strip_prefix("``\"")?.strip_suffix("\"``")
     }
 }
 
+// *** Interpolated string ***
+
+// '"a {x} b"' - each piece of text is a string literal of its own, and there is always one more
+// of them than there are expressions
+#[derive(Clone, Debug, PartialEq)]
+pub struct InterpolatedString<'input> {
+    pub strings: Vec<StringLit<'input>>,
+    pub exprs: Vec<SimpleExpr<'input>>,
+}
+
 // *** Char literal ***
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct CharLit<'input> {
     pub unparsed: Cow<'input, str>,
-    parsed: Option<char>,
+    parsed: char,
 }
 
 impl<'input> CharLit<'input> {
     pub fn from_str(s: &'input str, has_escapes: bool) -> Self {
-        let unparsed = Cow::Borrowed(s);
-
-        // if no escapes, then we are already parsed
-        let parsed = if has_escapes {
-            None
-        } else {
-            Some(unparsed.chars().nth(1).unwrap())
-        };
-
-        Self { unparsed, parsed }
+        Self {
+            unparsed: Cow::Borrowed(s),
+            parsed: Self::parse(s, has_escapes),
+        }
     }
 
     pub fn from_string(s: String, has_escapes: bool) -> Self {
-        let unparsed: Cow<'_, str> = Cow::Owned(s);
+        Self {
+            parsed: Self::parse(&s, has_escapes),
+            unparsed: Cow::Owned(s),
+        }
+    }
 
-        // if no escapes, then we are already parsed
+    fn parse(s: &str, has_escapes: bool) -> char {
+        // if no escapes, then it is the only char between the quotes
         let parsed = if has_escapes {
-            None
+            unescape(unquote(s)).chars().next()
         } else {
-            Some(unparsed.chars().nth(1).unwrap())
+            s.chars().nth(1)
         };
+        parsed.unwrap()
+    }
 
-        Self { unparsed, parsed }
+    // The char, with its escape decoded
+    pub fn value(&self) -> char {
+        self.parsed
     }
 }
 
 // *** Type ***
 
-// TODO: Flesh this out
 #[derive(Clone, Debug, PartialEq)]
 pub enum Type<'input> {
+    // Built in or declared type, type parameter, or type alias (which the checks resolve)
     Simple(Ident<'input>),
+    // Type with type arguments: 'list[T]'
+    Generic(Ident<'input>, Vec<Type<'input>>),
 }
 
+// The name of the type, without any type arguments
 impl AsRef<str> for Type<'_> {
     fn as_ref(&self) -> &str {
         match self {
-            Type::Simple(ident) => ident.as_ref(),
+            Type::Simple(ident) | Type::Generic(ident, _) => ident.as_ref(),
         }
     }
 }
 
+impl fmt::Display for Type<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Simple(ident) => f.write_str(ident.as_ref()),
+            Type::Generic(ident, args) => {
+                write!(f, "{}[", ident.as_ref())?;
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                f.write_str("]")
+            }
+        }
+    }
+}
+
+impl<'input> Type<'input> {
+    // Types that were parsed as expressions ('list[T]' looks just like indexing)
+    pub fn from_expr(expr: &SimpleExpr<'input>) -> Option<Self> {
+        match expr {
+            SimpleExpr::Ident(ident) => Some(Type::Simple(ident.clone())),
+            SimpleExpr::Unit => Some(Type::Simple(Ident::from_str("()"))),
+            SimpleExpr::Index(index) => match &index.target {
+                SimpleExpr::Ident(ident) => {
+                    let args = index
+                        .indexes
+                        .iter()
+                        .map(Type::from_expr)
+                        .collect::<Option<Vec<_>>>()?;
+                    Some(Type::Generic(ident.clone(), args))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+// 'T' or 'T: <interface>'
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeParam<'input> {
+    pub name: Ident<'input>,
+    pub bound: Option<Type<'input>>,
+}
+
 // *** Expressions ***
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Expr<'input> {
     If(If<'input>),
+    Match(Match<'input>),
     Simple(SimpleExpr<'input>),
 }
 
@@ -138,16 +283,42 @@ pub enum SimpleExpr<'input> {
     Ident(Ident<'input>),
     IntLit(IntLit),
     StringLit(StringLit<'input>),
+    InterpolatedString(Box<InterpolatedString<'input>>),
     CharLit(CharLit<'input>),
     BoolLit(BoolLit),
+    // The value a method was called on
+    SelfRef,
+    // '()', the only value of the unit type
+    Unit,
     Field(Box<Field<'input>>),
     Call(Box<Call<'input>>),
+    Index(Box<Index<'input>>),
     IfThenElse(Box<IfThenElse<'input>>),
     BoolCond(Box<BoolCond<'input>>),
+    BinaryOp(Box<BinaryOp<'input>>),
+    Try(Box<Try<'input>>),
+    With(Box<With<'input>>),
+    Comprehension(Box<Comprehension<'input>>),
+    // 'yield <value>' - the value is optional
+    Yield(Box<Option<SimpleExpr<'input>>>),
+    Await(Box<SimpleExpr<'input>>),
     // Expression in parens - should be rare
     Expr(Box<Expr<'input>>),
 }
 
+impl<'input> SimpleExpr<'input> {
+    // A 'match' in parens, which is how one is written where a value is expected
+    pub fn as_match(&self) -> Option<&Match<'input>> {
+        match self {
+            SimpleExpr::Expr(expr) => match expr.as_ref() {
+                Expr::Match(match_) => Some(match_),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
 // *** Bool Conditional ***
 
 #[derive(Clone, Debug, PartialEq)]
@@ -157,6 +328,50 @@ pub enum BoolCond<'input> {
     Or(SimpleExpr<'input>, SimpleExpr<'input>),
 }
 
+// *** Binary Operators ***
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOperator {
+    Equal,
+    NotEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOperator {
+    // As written in the source (and in Go)
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOperator::Equal => "==",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "/",
+        }
+    }
+
+    // Method a user defined type implements to support the operator ('!=' is 'not __equal__')
+    pub fn magic_method(self) -> &'static str {
+        match self {
+            BinaryOperator::Equal | BinaryOperator::NotEqual => "__equal__",
+            BinaryOperator::Add => "__add__",
+            BinaryOperator::Subtract => "__sub__",
+            BinaryOperator::Multiply => "__mul__",
+            BinaryOperator::Divide => "__div__",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryOp<'input> {
+    pub op: BinaryOperator,
+    pub lhs: SimpleExpr<'input>,
+    pub rhs: SimpleExpr<'input>,
+}
+
 // *** If/Then/Else ***
 
 #[derive(Clone, Debug, PartialEq)]
@@ -166,6 +381,67 @@ pub struct IfThenElse<'input> {
     pub else_: SimpleExpr<'input>,
 }
 
+// *** Try/Catch ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Try<'input> {
+    pub body: TryBody<'input>,
+    // 'catch as <name>' - the error is named 'err' if not given
+    pub err_name: Option<Ident<'input>>,
+    pub catch_body: Block<'input>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TryBody<'input> {
+    // 'try <expr> catch' - the expression is unwrapped
+    Expr(SimpleExpr<'input>),
+    // 'try: ... catch' - values are unwrapped by 'name <- expr' statements
+    Block(Block<'input>),
+}
+
+// 'name <- value' - only valid in a try block
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unwrap<'input> {
+    pub name: Ident<'input>,
+    pub value: SimpleExpr<'input>,
+}
+
+// *** With ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct With<'input> {
+    // The context, which is entered before the block and exited after it
+    pub context: SimpleExpr<'input>,
+    // 'as <name>'
+    pub name: Option<Ident<'input>>,
+    pub body: Block<'input>,
+}
+
+// *** Comprehension ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comprehension<'input> {
+    pub kind: ComprehensionKind<'input>,
+    // 'for <name> in <source>'
+    pub name: Ident<'input>,
+    pub source: SimpleExpr<'input>,
+    // 'if <cond>'
+    pub cond: Option<SimpleExpr<'input>>,
+}
+
+// What is built and the expressions that give its elements
+#[derive(Clone, Debug, PartialEq)]
+pub enum ComprehensionKind<'input> {
+    // '(<elem> <- for ...)' - a lazy iterator
+    Iter(SimpleExpr<'input>),
+    // '[<elem> <- for ...]'
+    List(SimpleExpr<'input>),
+    // '{<key>: <value> <- for ...}'
+    Map(SimpleExpr<'input>, SimpleExpr<'input>),
+    // '{|<elem> <- for ...|}'
+    Set(SimpleExpr<'input>),
+}
+
 // *** Field ***
 
 #[derive(Clone, Debug, PartialEq)]
@@ -174,6 +450,14 @@ pub struct Field<'input> {
     pub field: Ident<'input>,
 }
 
+// *** Index ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Index<'input> {
+    pub target: SimpleExpr<'input>,
+    pub indexes: Vec<SimpleExpr<'input>>,
+}
+
 // *** Call ***
 
 #[derive(Clone, Debug, PartialEq)]
@@ -198,14 +482,45 @@ pub struct Block<'input> {
 #[derive(Clone, Debug, PartialEq)]
 pub enum StmtOrExpr<'input> {
     Func(Func<'input>),
+    Let(Let<'input>),
+    Unwrap(Unwrap<'input>),
     Expr(Expr<'input>),
 }
 
+// *** Let ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Let<'input> {
+    pub target: LetTarget<'input>,
+    // 'let var' - the binding can be reassigned
+    pub mutable: bool,
+    pub let_type: Option<Type<'input>>,
+    pub value: SimpleExpr<'input>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LetTarget<'input> {
+    Ident(Ident<'input>),
+    // Only valid in methods (typically to initialize fields in 'new')
+    SelfField(Ident<'input>),
+}
+
+// Top level only - the value must be known at compile time
+#[derive(Clone, Debug, PartialEq)]
+pub struct Const<'input> {
+    pub name: Ident<'input>,
+    pub const_type: Option<Type<'input>>,
+    pub value: SimpleExpr<'input>,
+}
+
 // *** Function ***
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Func<'input> {
+    // An 'async' func runs on its own goroutine, and calling it returns a 'Future'
+    pub is_async: bool,
     pub name: Ident<'input>,
+    pub type_params: Vec<TypeParam<'input>>,
     pub args: Vec<FuncArg<'input>>,
     pub body: FuncBody<'input>,
 }
@@ -240,11 +555,164 @@ pub enum ElseBody<'input> {
     Block(Block<'input>),
 }
 
+// *** Match ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match<'input> {
+    pub expr: SimpleExpr<'input>,
+    pub arms: Vec<MatchArm<'input>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchArm<'input> {
+    pub pattern: Pattern<'input>,
+    pub body: SimpleExpr<'input>,
+}
+
+// *** Pattern ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern<'input> {
+    // '_' - matches anything, binds nothing
+    Wildcard,
+    // Matches anything and binds it to the name
+    Bind(Ident<'input>),
+    IntLit(IntLit),
+    StringLit(StringLit<'input>),
+    CharLit(CharLit<'input>),
+    BoolLit(BoolLit),
+    // Comma separated alternatives - matches if any of them match
+    Alt(Vec<Pattern<'input>>),
+    // Binds the entire matched value to the name
+    As(Box<Pattern<'input>>, Ident<'input>),
+    Type(TypePattern<'input>),
+    Ctor(CtorPattern<'input>),
+}
+
+// Matches if the value is of the given type and binds it to the name (if given)
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypePattern<'input> {
+    pub name: Option<Ident<'input>>,
+    pub pattern_type: Type<'input>,
+}
+
+// Matches a type constructor and then each of its fields against the sub-patterns
+#[derive(Clone, Debug, PartialEq)]
+pub struct CtorPattern<'input> {
+    pub name: Ident<'input>,
+    pub args: Vec<Pattern<'input>>,
+}
+
+// *** Struct ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct<'input> {
+    pub name: Ident<'input>,
+    pub type_params: Vec<TypeParam<'input>>,
+    pub fields: Vec<StructField<'input>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructField<'input> {
+    pub name: Ident<'input>,
+    pub field_type: Type<'input>,
+}
+
+// *** Enum ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Enum<'input> {
+    pub name: Ident<'input>,
+    pub type_params: Vec<TypeParam<'input>>,
+    pub variants: Vec<Variant<'input>>,
+}
+
+impl Enum<'_> {
+    // No variant has a payload, so it can be represented as an integer
+    pub fn is_c_like(&self) -> bool {
+        self.variants
+            .iter()
+            .all(|variant| variant.fields.is_empty())
+    }
+
+    // Discriminant of each variant - explicit values are optional and the rest count up from
+    // the previous one (starting from 0)
+    pub fn values(&self) -> Vec<i32> {
+        let mut next = 0;
+        self.variants
+            .iter()
+            .map(|variant| {
+                let value = variant.value.as_ref().map_or(next, |value| value.value);
+                next = value.wrapping_add(1);
+                value
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant<'input> {
+    pub name: Ident<'input>,
+    // Types of the (unnamed) fields of the payload, if any
+    pub fields: Vec<Type<'input>>,
+    // Explicit discriminant - only for enums without payloads
+    pub value: Option<IntLit>,
+    // End of a range given as the discriminant ('= 0..3'), which the checks reject
+    pub range_end: Option<IntLit>,
+}
+
+// *** Interface ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interface<'input> {
+    pub name: Ident<'input>,
+    pub methods: Vec<MethodSpec<'input>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MethodSpec<'input> {
+    pub name: Ident<'input>,
+    pub arg_types: Vec<Type<'input>>,
+    pub ret_type: Option<Type<'input>>,
+}
+
+// *** Impl ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Impl<'input> {
+    pub name: Ident<'input>,
+    // 'impl Box[T]' - the type parameters of a generic type (bounds are from its declaration)
+    pub type_params: Vec<Ident<'input>>,
+    // 'impl <interface> for <name>'
+    pub interface: Option<Ident<'input>>,
+    // A function named 'new' is the constructor, the rest are methods
+    pub funcs: Vec<Func<'input>>,
+}
+
+// *** Type declaration ***
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeDecl<'input> {
+    pub name: Ident<'input>,
+    // 'type X = Y' is another name for the same type, 'type X Y' is a distinct type (a newtype)
+    // with the same representation
+    pub alias: bool,
+    pub target: Type<'input>,
+}
+
 // *** Top level ***
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Decl<'input> {
     Func(Func<'input>),
+    Struct(Struct<'input>),
+    Enum(Enum<'input>),
+    Interface(Interface<'input>),
+    Impl(Impl<'input>),
+    Type(TypeDecl<'input>),
+    // Always binds a name (not a field)
+    Let(Let<'input>),
+    Const(Const<'input>),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -0,0 +1,3383 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::mem;
+
+use crate::exhaustive::Exhaustiveness;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.message),
+            Severity::Warning => write!(f, "warning: {}", self.message),
+        }
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+// What code generation needs to know from the checks. Nodes are identified by address, so this
+// is only valid for the declarations that were checked
+#[derive(Debug, Default)]
+pub struct Annotations {
+    // Binary operators and indexing on user defined types -> the magic method to call instead
+    magic_methods: HashMap<usize, &'static str>,
+    // Conditions that aren't already 'bool' -> how to convert them
+    conditions: HashMap<usize, Truthiness>,
+    // Values and patterns of generic types -> their type arguments, which Go can't infer for
+    // composite literals and type switches
+    type_args: HashMap<usize, Vec<ast::Type<'static>>>,
+    // 'catch' values of type 'error' in functions returning 'Result[T]' -> 'T', as they are
+    // returned wrapped in 'Err'
+    wrapped_errors: HashMap<usize, ast::Type<'static>>,
+    // Comprehensions -> how their source is iterated over and the type they build
+    comprehensions: HashMap<usize, (Iteration, ast::Type<'static>)>,
+    // Iterator comprehensions -> what the struct they are lowered to needs besides the source
+    iter_structs: HashMap<usize, IterStruct>,
+    // 'with' and 'match' values of 'let's -> the type of the 'let', which is declared before the
+    // Go block or switch the value is assigned in
+    let_types: HashMap<usize, ast::Type<'static>>,
+    // Interpolated strings -> the types of their expressions (if known), which decide how they
+    // are formatted
+    interpolations: HashMap<usize, Vec<Option<ast::Type<'static>>>>,
+    // Method calls on prelude types, whose Go names are exported by the runtime package
+    runtime_methods: HashSet<usize>,
+    // Local 'let's whose name is never read, which Go doesn't allow unless it is assigned to '_'
+    unused_lets: HashSet<usize>,
+    // Calls of 'map' on 'Option' or 'Result' -> the runtime func that does it, as Go methods can't
+    // have type parameters of their own
+    map_funcs: HashMap<usize, &'static str>,
+    // Calls of funcs and methods -> the value given for each of their args, in declaration order
+    call_args: HashMap<usize, Vec<ArgValue>>,
+    // Funcs of a type itself ('Day.from_str'), which are Go funcs named after the type
+    static_members: HashSet<usize>,
+}
+
+// How a value that isn't a 'bool' is converted when used as a condition
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Truthiness {
+    // Ints and chars: true unless zero
+    NonZero,
+    // Strings, lists and maps: true unless empty
+    NonEmpty,
+    // Enums with a 'None' variant (and no '__bool__'): true unless 'None'
+    NotNone,
+    // User defined types: the result of '__bool__'
+    Method,
+}
+
+// How the source of a comprehension is iterated over
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Iteration {
+    // Lists: their elements
+    Elements,
+    // Maps and sets: their keys
+    Keys,
+    // Iterators: their values, until 'Next' says there are no more
+    Next,
+}
+
+// The struct an iterator comprehension is lowered to keeps its own copy of what the comprehension
+// refers to, as its 'Next' method is a Go func of its own
+#[derive(Clone, Debug, Default)]
+pub struct IterStruct {
+    // Locals (and 'self') used by the element and the condition, and their types
+    pub locals: Vec<(String, ast::Type<'static>)>,
+    // The type parameters in scope, which the types of the locals may mention
+    pub type_params: Vec<ast::TypeParam<'static>>,
+}
+
+// Where the value of a func arg comes from in a call
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArgValue {
+    // The call arg at this index
+    Given(usize),
+    // The default value of the arg, identified like the annotated nodes. Each call gets its own
+    // copy of it
+    Default(usize),
+}
+
+fn node_key<T>(node: &T) -> usize {
+    node as *const T as usize
+}
+
+impl Annotations {
+    pub fn magic_method<T>(&self, node: &T) -> Option<&'static str> {
+        self.magic_methods.get(&node_key(node)).copied()
+    }
+
+    pub fn truthiness(&self, cond: &ast::SimpleExpr<'_>) -> Option<Truthiness> {
+        self.conditions.get(&node_key(cond)).copied()
+    }
+
+    pub fn type_args<T>(&self, node: &T) -> Option<&[ast::Type<'static>]> {
+        self.type_args.get(&node_key(node)).map(Vec::as_slice)
+    }
+
+    pub fn is_runtime_method(&self, field: &ast::Field<'_>) -> bool {
+        self.runtime_methods.contains(&node_key(field))
+    }
+
+    pub fn is_unused(&self, let_: &ast::Let<'_>) -> bool {
+        self.unused_lets.contains(&node_key(let_))
+    }
+
+    pub fn iter_struct(&self, comprehension: &ast::Comprehension<'_>) -> Option<&IterStruct> {
+        self.iter_structs.get(&node_key(comprehension))
+    }
+
+    pub fn map_func(&self, call: &ast::Call<'_>) -> Option<&'static str> {
+        self.map_funcs.get(&node_key(call)).copied()
+    }
+
+    pub fn is_static_member(&self, field: &ast::Field<'_>) -> bool {
+        self.static_members.contains(&node_key(field))
+    }
+
+    pub fn wrapped_error(&self, expr: &ast::SimpleExpr<'_>) -> Option<&ast::Type<'static>> {
+        self.wrapped_errors.get(&node_key(expr))
+    }
+
+    pub fn with_type(&self, with: &ast::With<'_>) -> Option<&ast::Type<'static>> {
+        self.let_types.get(&node_key(with))
+    }
+
+    pub fn match_type(&self, match_: &ast::Match<'_>) -> Option<&ast::Type<'static>> {
+        self.let_types.get(&node_key(match_))
+    }
+
+    pub fn interpolation_types(
+        &self,
+        string: &ast::InterpolatedString<'_>,
+    ) -> &[Option<ast::Type<'static>>] {
+        self.interpolations
+            .get(&node_key(string))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn call_args(&self, call: &ast::Call<'_>) -> Option<&[ArgValue]> {
+        self.call_args.get(&node_key(call)).map(Vec::as_slice)
+    }
+
+    pub fn default_val_key(default_val: &ast::SimpleExpr<'_>) -> usize {
+        node_key(default_val)
+    }
+
+    pub fn comprehension(
+        &self,
+        comprehension: &ast::Comprehension<'_>,
+    ) -> Option<(Iteration, &ast::Type<'static>)> {
+        self.comprehensions
+            .get(&node_key(comprehension))
+            .map(|(iteration, type_)| (*iteration, type_))
+    }
+}
+
+// Annotations outlive the source the types are borrowed from
+fn owned_type(type_: &ast::Type<'_>) -> ast::Type<'static> {
+    let owned = |name: &ast::Ident<'_>| ast::Ident::from_string(name.as_ref().to_string());
+
+    match type_ {
+        ast::Type::Simple(name) => ast::Type::Simple(owned(name)),
+        ast::Type::Generic(name, args) => {
+            ast::Type::Generic(owned(name), args.iter().map(owned_type).collect())
+        }
+    }
+}
+
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    pub annotations: Annotations,
+}
+
+// Semantic checks that run after parsing and before code generation. The prelude is in scope,
+// but isn't checked itself
+pub fn analyze(prelude: &[ast::Decl<'_>], decls: &[ast::Decl<'_>]) -> Analysis {
+    let mut checker = Checker::new();
+    checker.check_file(prelude, decls);
+
+    Analysis {
+        diagnostics: checker.diagnostics,
+        annotations: checker.annotations,
+    }
+}
+
+pub fn check(prelude: &[ast::Decl<'_>], decls: &[ast::Decl<'_>]) -> Vec<Diagnostic> {
+    analyze(prelude, decls).diagnostics
+}
+
+// What a name after a '.' refers to
+enum Member<'a, 'input> {
+    Field(ast::Type<'input>),
+    Method(&'a ast::Func<'input>),
+    // Method generated by the compiler and its return type
+    Builtin(ast::Type<'input>),
+    // Method of an interface
+    Spec(&'a ast::MethodSpec<'input>),
+    // 'map' of 'Option' and 'Result' (the runtime func that does it), whose return type depends
+    // on the func it is given
+    Map(&'static str),
+    Unknown,
+}
+
+fn simple_type(name: &str) -> ast::Type<'_> {
+    ast::Type::Simple(ast::Ident::from_str(name))
+}
+
+// Built in types have a capitalized alias (the name used for literals is the canonical one)
+fn canonical_type(name: &str) -> &str {
+    match name {
+        "Int" => "int",
+        "String" => "str",
+        "Bool" => "bool",
+        name => name,
+    }
+}
+
+fn same_type(type_: &ast::Type<'_>, other: &ast::Type<'_>) -> bool {
+    match (type_, other) {
+        (ast::Type::Simple(name), ast::Type::Simple(other)) => {
+            canonical_type(name.as_ref()) == canonical_type(other.as_ref())
+        }
+        (ast::Type::Generic(name, args), ast::Type::Generic(other, other_args)) => {
+            name.as_ref() == other.as_ref()
+                && args.len() == other_args.len()
+                && args
+                    .iter()
+                    .zip(other_args)
+                    .all(|(arg, other)| same_type(arg, other))
+        }
+        _ => false,
+    }
+}
+
+// Replaces the type parameters in the type with the types inferred for them
+fn substitute<'input>(
+    type_: &ast::Type<'input>,
+    inferred: &HashMap<&str, ast::Type<'input>>,
+) -> ast::Type<'input> {
+    match type_ {
+        ast::Type::Simple(name) => inferred.get(name.as_ref()).unwrap_or(type_).clone(),
+        ast::Type::Generic(name, args) => ast::Type::Generic(
+            name.clone(),
+            args.iter().map(|arg| substitute(arg, inferred)).collect(),
+        ),
+    }
+}
+
+// True if the type parameter appears anywhere in the type
+fn mentions(type_: &ast::Type<'_>, param: &str) -> bool {
+    match type_ {
+        ast::Type::Simple(name) => name.as_ref() == param,
+        ast::Type::Generic(name, args) => {
+            name.as_ref() == param || args.iter().any(|arg| mentions(arg, param))
+        }
+    }
+}
+
+// Signature as written in messages: '(<arg types>) -> <return type>'
+fn signature<'b>(
+    arg_types: impl Iterator<Item = &'b ast::Type<'b>>,
+    ret_type: Option<&ast::Type<'_>>,
+) -> String {
+    let args = arg_types
+        .map(|type_| type_.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match ret_type {
+        Some(ret_type) => format!("({args}) -> {ret_type}"),
+        None => format!("({args})"),
+    }
+}
+
+// The types a coroutine yields and is resumed with
+fn coroutine_types<'t, 'input>(
+    type_: &'t ast::Type<'input>,
+) -> Option<(&'t ast::Type<'input>, &'t ast::Type<'input>)> {
+    match type_ {
+        ast::Type::Generic(name, args) if name.as_ref() == "coroutine" => match args.as_slice() {
+            [out, in_] => Some((out, in_)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// The type of calling the func: what an 'async' one returns is wrapped in a 'Future'
+fn return_type<'input>(func: &ast::Func<'input>) -> Option<ast::Type<'input>> {
+    match &func.body {
+        ast::FuncBody::Block(type_, _) if func.is_async => Some(ast::Type::Generic(
+            ast::Ident::from_str("Future"),
+            vec![type_.clone().unwrap_or_else(|| simple_type("()"))],
+        )),
+        _ => value_type(func),
+    }
+}
+
+// Declared return type of a function, if any: the type of the value its body evaluates to
+fn value_type<'input>(func: &ast::Func<'input>) -> Option<ast::Type<'input>> {
+    match &func.body {
+        ast::FuncBody::Block(type_, _) => type_.clone(),
+        ast::FuncBody::Expr(_) => None,
+    }
+}
+
+// The args of a func: their names and the keys of their default values
+fn call_params<'f>(func: &'f ast::Func<'_>) -> Vec<(&'f str, Option<usize>)> {
+    func.args
+        .iter()
+        .map(|arg| (arg.name.as_ref(), arg.default_val.as_ref().map(node_key)))
+        .collect()
+}
+
+// What calls to a func declared in a block are checked against (the declaration itself isn't
+// kept, as blocks are checked wherever they are found)
+struct LocalFunc<'input> {
+    name: String,
+    params: Vec<(String, Option<usize>)>,
+    arg_types: Vec<ast::Type<'input>>,
+    ret_type: Option<ast::Type<'input>>,
+}
+
+// A local name and its type, if known
+type LocalType<'input> = (String, Option<ast::Type<'input>>);
+
+struct Checker<'a, 'input> {
+    exhaustiveness: Exhaustiveness,
+    // Top level declarations by name
+    structs: HashMap<&'a str, &'a ast::Struct<'input>>,
+    enums: HashMap<&'a str, &'a ast::Enum<'input>>,
+    interfaces: HashMap<&'a str, &'a ast::Interface<'input>>,
+    // Variant name -> the enum it belongs to and the variant
+    variants: HashMap<&'a str, (&'a ast::Enum<'input>, &'a ast::Variant<'input>)>,
+    top_funcs: HashMap<&'a str, &'a ast::Func<'input>>,
+    // Type alias -> the type it names
+    aliases: HashMap<&'a str, &'a ast::Type<'input>>,
+    // Newtype -> the type it has the representation of
+    newtypes: HashMap<&'a str, &'a ast::Type<'input>>,
+    top_lets: HashMap<&'a str, &'a ast::Let<'input>>,
+    consts: HashMap<&'a str, &'a ast::Const<'input>>,
+    // Top level funcs, methods ('S.get') and bindings -> the ones they refer to, which decide the
+    // order bindings are initialized in
+    top_refs: HashMap<String, Vec<String>>,
+    // The top level func, method or binding we are inside of
+    top_item: Option<String>,
+    // Type name -> functions of its impl blocks
+    methods: HashMap<&'a str, Vec<&'a ast::Func<'input>>>,
+    // Structs and enums declared by the prelude
+    runtime_types: HashSet<&'a str>,
+    // Names of the functions we are currently inside of (innermost last)
+    funcs: Vec<String>,
+    // The type 'self' refers to, if we are inside a method
+    self_type: Option<&'a ast::Ident<'input>>,
+    // Return types of the functions we are currently inside of (innermost last)
+    ret_types: Vec<Option<ast::Type<'input>>>,
+    // Set while checking the statements of a 'try' block (but not blocks nested in it), which
+    // are the only place '<-' can be used
+    in_try: bool,
+    // Set while checking the body of an 'async' func, the only place 'await' can be used
+    in_async: bool,
+    // Local names and their types, if known (innermost scope last)
+    scopes: Vec<HashMap<String, Option<ast::Type<'input>>>>,
+    // Funcs declared in each of the scopes, which calls are matched to like top level funcs
+    local_funcs: Vec<HashMap<String, LocalFunc<'input>>>,
+    // Names bound by 'let's in each of the scopes that haven't been read yet -> the 'let'
+    unread_lets: Vec<HashMap<String, usize>>,
+    // Iterator comprehensions being checked (innermost last): the first scope of each and the
+    // locals from outside of it that it refers to
+    iter_locals: Vec<(usize, Vec<LocalType<'input>>)>,
+    // Set to the scope of the args of a func while checking their default values
+    default_scope: Option<usize>,
+    // Type parameters in scope and their bounds (innermost function last)
+    type_params: Vec<HashMap<String, Option<ast::Type<'input>>>>,
+    annotations: Annotations,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a, 'input> Checker<'a, 'input> {
+    fn new() -> Self {
+        Self {
+            exhaustiveness: Exhaustiveness::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            interfaces: HashMap::new(),
+            variants: HashMap::new(),
+            top_funcs: HashMap::new(),
+            aliases: HashMap::new(),
+            newtypes: HashMap::new(),
+            top_lets: HashMap::new(),
+            consts: HashMap::new(),
+            top_refs: HashMap::new(),
+            top_item: None,
+            methods: HashMap::new(),
+            runtime_types: HashSet::new(),
+            funcs: Vec::new(),
+            self_type: None,
+            ret_types: Vec::new(),
+            in_try: false,
+            in_async: false,
+            scopes: Vec::new(),
+            local_funcs: Vec::new(),
+            unread_lets: Vec::new(),
+            iter_locals: Vec::new(),
+            default_scope: None,
+            type_params: Vec::new(),
+            annotations: Annotations::default(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, severity: Severity, message: String) {
+        let message = match self.funcs.last() {
+            Some(func) => format!("in func `{func}`: {message}"),
+            None => message,
+        };
+        self.diagnostics.push(Diagnostic { severity, message });
+    }
+
+    fn lookup_local(&self, name: &str) -> Option<&Option<ast::Type<'input>>> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    // Records a reference to a top level func or binding, unless a local name shadows it (the
+    // first scope holds the top level bindings)
+    fn refer_top(&mut self, name: &str) {
+        let Some(item) = &self.top_item else {
+            return;
+        };
+        let top_level = self.top_funcs.contains_key(name)
+            || self.top_lets.contains_key(name)
+            || self.consts.contains_key(name);
+        let shadowed = self
+            .scopes
+            .iter()
+            .skip(1)
+            .any(|scope| scope.contains_key(name));
+
+        if top_level && !shadowed {
+            self.top_refs
+                .entry(item.clone())
+                .or_default()
+                .push(name.to_string());
+        }
+    }
+
+    // Records a call of a method, which is named after the type it is declared for
+    fn refer_method(&mut self, method: &ast::Func<'input>) {
+        let Some(item) = &self.top_item else {
+            return;
+        };
+        let owner = self.methods.iter().find_map(|(type_name, funcs)| {
+            funcs
+                .iter()
+                .any(|func| std::ptr::eq(*func, method))
+                .then_some(*type_name)
+        });
+
+        if let Some(owner) = owner {
+            self.top_refs
+                .entry(item.clone())
+                .or_default()
+                .push(format!("{owner}.{}", method.name.as_ref()));
+        }
+    }
+
+    // Index of the innermost scope the name is declared in
+    fn scope_of(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rposition(|scope| scope.contains_key(name))
+    }
+
+    fn push_scope(&mut self, scope: HashMap<String, Option<ast::Type<'input>>>) {
+        self.scopes.push(scope);
+        self.local_funcs.push(HashMap::new());
+        self.unread_lets.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop();
+        self.local_funcs.pop();
+        if let Some(unread) = self.unread_lets.pop() {
+            self.annotations.unused_lets.extend(unread.into_values());
+        }
+    }
+
+    // Marks the 'let' the name refers to (if any) as read
+    fn read_local(&mut self, name: &str) {
+        let Some(idx) = self.scope_of(name) else {
+            return;
+        };
+        self.unread_lets[idx].remove(name);
+
+        // The first scope holds the top level bindings, which the iterator structs can use as is
+        if idx > 0 {
+            let type_ = self.scopes[idx][name].clone();
+            self.capture(idx, name, type_);
+        }
+    }
+
+    // Records a local the iterator comprehensions it is declared outside of refer to
+    fn capture(&mut self, scope: usize, name: &str, type_: Option<ast::Type<'input>>) {
+        for (first_scope, locals) in &mut self.iter_locals {
+            if scope < *first_scope && !locals.iter().any(|(local, _)| local == name) {
+                locals.push((name.to_string(), type_.clone()));
+            }
+        }
+    }
+
+    fn bind_local(&mut self, name: &str, type_: Option<ast::Type<'input>>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), type_);
+        }
+        if let Some(funcs) = self.local_funcs.last_mut() {
+            funcs.remove(name);
+        }
+    }
+
+    // Local funcs can be shadowed by any other local name
+    fn lookup_local_func(&self, name: &str) -> Option<&LocalFunc<'input>> {
+        self.local_funcs.get(self.scope_of(name)?)?.get(name)
+    }
+
+    fn bind_local_func(&mut self, func: &ast::Func<'input>) {
+        let name = func.name.as_ref();
+        self.bind_local(name, None);
+        let local_func = LocalFunc {
+            name: name.to_string(),
+            params: call_params(func)
+                .into_iter()
+                .map(|(name, default_val)| (name.to_string(), default_val))
+                .collect(),
+            arg_types: func.args.iter().map(|arg| arg.arg_type.clone()).collect(),
+            ret_type: return_type(func),
+        };
+        if let Some(funcs) = self.local_funcs.last_mut() {
+            funcs.insert(name.to_string(), local_func);
+        }
+    }
+
+    // Collects top level declarations so they can be referenced before they are declared
+    fn declare(&mut self, decls: &[&'a ast::Decl<'input>]) {
+        for decl in decls {
+            match decl {
+                ast::Decl::Func(func) => {
+                    self.top_funcs.insert(func.name.as_ref(), func);
+                }
+                ast::Decl::Struct(struct_) => {
+                    let name = struct_.name.as_ref();
+                    self.declare_type(name);
+                    self.structs.insert(name, struct_);
+
+                    // A struct is a type with a single constructor as far as matching goes
+                    self.exhaustiveness
+                        .add_enum(name, &[(name, struct_.fields.len())]);
+                }
+                ast::Decl::Enum(enum_) => {
+                    let name = enum_.name.as_ref();
+                    self.declare_type(name);
+                    self.enums.insert(name, enum_);
+
+                    let variants: Vec<_> = enum_
+                        .variants
+                        .iter()
+                        .map(|variant| (variant.name.as_ref(), variant.fields.len()))
+                        .collect();
+                    self.exhaustiveness.add_enum(name, &variants);
+                }
+                ast::Decl::Interface(interface) => {
+                    let name = interface.name.as_ref();
+                    self.declare_type(name);
+                    self.interfaces.insert(name, interface);
+                }
+                ast::Decl::Type(type_decl) => {
+                    let name = type_decl.name.as_ref();
+                    self.declare_type(name);
+                    if type_decl.alias {
+                        self.aliases.insert(name, &type_decl.target);
+                    } else {
+                        self.newtypes.insert(name, &type_decl.target);
+                    }
+                }
+                ast::Decl::Impl(_) | ast::Decl::Let(_) | ast::Decl::Const(_) => {}
+            }
+        }
+
+        // Variants are in scope everywhere, and in Go each one is a type of its own
+        for decl in decls {
+            if let ast::Decl::Enum(enum_) = decl {
+                for variant in &enum_.variants {
+                    let name = variant.name.as_ref();
+                    if self.variants.contains_key(name) || self.is_type(name) {
+                        self.report(
+                            Severity::Error,
+                            format!("variant `{name}` is declared more than once"),
+                        );
+                    }
+                    self.variants.insert(name, (enum_, variant));
+                }
+            }
+        }
+
+        for decl in decls {
+            if let ast::Decl::Impl(impl_) = decl {
+                let name = impl_.name.as_ref();
+                if self.has_impls(name) {
+                    self.methods
+                        .entry(name)
+                        .or_default()
+                        .extend(impl_.funcs.iter());
+                } else if self.interfaces.contains_key(name) {
+                    self.report(
+                        Severity::Error,
+                        format!("interface `{name}` can't have an impl, only types can"),
+                    );
+                } else {
+                    self.report(Severity::Error, format!("impl for unknown type `{name}`"));
+                }
+            }
+        }
+    }
+
+    // Top level bindings share their namespace with funcs and types
+    fn declare_bindings(&mut self, decls: &[&'a ast::Decl<'input>]) {
+        for decl in decls {
+            let name = match decl {
+                ast::Decl::Let(let_) => match &let_.target {
+                    ast::LetTarget::Ident(name) => name.as_ref(),
+                    ast::LetTarget::SelfField(_) => continue,
+                },
+                ast::Decl::Const(const_) => const_.name.as_ref(),
+                _ => continue,
+            };
+
+            if self.top_funcs.contains_key(name)
+                || self.top_lets.contains_key(name)
+                || self.consts.contains_key(name)
+                || self.variants.contains_key(name)
+                || self.is_type(name)
+            {
+                self.report(
+                    Severity::Error,
+                    format!("`{name}` is declared more than once"),
+                );
+            }
+
+            match decl {
+                ast::Decl::Let(let_) => {
+                    self.top_lets.insert(name, let_);
+                }
+                ast::Decl::Const(const_) => {
+                    self.consts.insert(name, const_);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn declare_type(&mut self, name: &str) {
+        if self.is_type(name) || self.aliases.contains_key(name) || self.newtypes.contains_key(name)
+        {
+            self.report(
+                Severity::Error,
+                format!("type `{name}` is declared more than once"),
+            );
+        }
+    }
+
+    // True if the name is a user defined type (or a type parameter, which behaves like its bound)
+    fn is_type(&self, name: &str) -> bool {
+        self.has_impls(name)
+            || self.interfaces.contains_key(name)
+            || self.lookup_type_param(name).is_some()
+    }
+
+    fn lookup_type_param(&self, name: &str) -> Option<&Option<ast::Type<'input>>> {
+        self.type_params
+            .iter()
+            .rev()
+            .find_map(|params| params.get(name))
+    }
+
+    // Type parameters of a struct or enum (None for any other type)
+    fn type_params_of(&self, name: &str) -> Option<&'a [ast::TypeParam<'input>]> {
+        if self.lookup_type_param(name).is_some() {
+            return None;
+        }
+
+        match (self.structs.get(name), self.enums.get(name)) {
+            (Some(struct_), _) => Some(&struct_.type_params),
+            (None, Some(enum_)) => Some(&enum_.type_params),
+            (None, None) => None,
+        }
+    }
+
+    // Type parameter -> type argument, for an instance of a generic type ('T' -> 'int' for
+    // 'Box[int]')
+    fn type_args_of(&self, type_: &ast::Type<'input>) -> HashMap<&'a str, ast::Type<'input>> {
+        match (type_, self.type_params_of(type_.as_ref())) {
+            (ast::Type::Generic(_, args), Some(params)) if args.len() == params.len() => params
+                .iter()
+                .map(|param| param.name.as_ref())
+                .zip(args.iter().cloned())
+                .collect(),
+            _ => HashMap::new(),
+        }
+    }
+
+    // The type of 'self' in an impl of the type: generic types are instantiated with their own
+    // type parameters
+    fn self_instance(&self, name: &ast::Ident<'input>) -> ast::Type<'input> {
+        match self.type_params_of(name.as_ref()) {
+            Some(params) if !params.is_empty() => ast::Type::Generic(
+                name.clone(),
+                params
+                    .iter()
+                    .map(|param| ast::Type::Simple(param.name.clone()))
+                    .collect(),
+            ),
+            _ => ast::Type::Simple(name.clone()),
+        }
+    }
+
+    // True if the name is a user defined type that methods can be declared on
+    fn has_impls(&self, name: &str) -> bool {
+        self.structs.contains_key(name) || self.enums.contains_key(name)
+    }
+
+    // "struct", "enum", "interface" or "type parameter" - for messages about user defined types
+    fn type_kind(&self, name: &str) -> &'static str {
+        if self.lookup_type_param(name).is_some() {
+            "type parameter"
+        } else if self.enums.contains_key(name) {
+            "enum"
+        } else if self.interfaces.contains_key(name) {
+            "interface"
+        } else {
+            "struct"
+        }
+    }
+
+    // Local names shadow variants
+    fn lookup_variant(
+        &self,
+        name: &str,
+    ) -> Option<(&'a ast::Enum<'input>, &'a ast::Variant<'input>)> {
+        match self.lookup_local(name) {
+            Some(_) => None,
+            None => self.variants.get(name).copied(),
+        }
+    }
+
+    fn member(&self, type_name: &str, name: &str) -> Member<'a, 'input> {
+        // Only what the bound declares can be used on a type parameter
+        if let Some(bound) = self.lookup_type_param(type_name) {
+            return match bound {
+                Some(bound) if self.interfaces.contains_key(bound.as_ref()) => {
+                    self.member(bound.as_ref(), name)
+                }
+                _ => Member::Unknown,
+            };
+        }
+
+        if let Some(field) = self.structs.get(type_name).and_then(|struct_| {
+            struct_
+                .fields
+                .iter()
+                .find(|field| field.name.as_ref() == name)
+        }) {
+            return Member::Field(field.field_type.clone());
+        }
+
+        match self
+            .methods
+            .get(type_name)
+            .and_then(|methods| methods.iter().find(|method| method.name.as_ref() == name))
+        {
+            Some(method) => Member::Method(method),
+            None if self.interfaces.contains_key(type_name) => {
+                match self.interfaces[type_name]
+                    .methods
+                    .iter()
+                    .find(|method| method.name.as_ref() == name)
+                {
+                    Some(method) => Member::Spec(method),
+                    None => Member::Unknown,
+                }
+            }
+            None if name == "str"
+                && self
+                    .enums
+                    .get(type_name)
+                    .is_some_and(|enum_| enum_.is_c_like()) =>
+            {
+                Member::Builtin(simple_type("str"))
+            }
+            None if name == "map"
+                && matches!(type_name, "Option" | "Result")
+                && self.runtime_types.contains(type_name) =>
+            {
+                Member::Map(if type_name == "Option" {
+                    "MapOption"
+                } else {
+                    "MapResult"
+                })
+            }
+            None => Member::Unknown,
+        }
+    }
+
+    // Funcs of the type itself and their arg and return types
+    fn static_member(
+        &self,
+        type_name: &str,
+        name: &str,
+    ) -> Option<(Vec<ast::Type<'input>>, ast::Type<'input>)> {
+        match self.enums.get(type_name) {
+            Some(enum_) if enum_.is_c_like() && name == "from_str" => Some((
+                vec![simple_type("str")],
+                ast::Type::Generic(
+                    ast::Ident::from_str("Option"),
+                    vec![ast::Type::Simple(enum_.name.clone())],
+                ),
+            )),
+            _ => None,
+        }
+    }
+
+    // Name of the type whose static member is accessed, if the target of the field is a type
+    fn static_target(&self, field: &ast::Field<'input>) -> Option<String> {
+        match &field.target {
+            ast::SimpleExpr::Ident(ident)
+                if self.lookup_local(ident.as_ref()).is_none() && self.is_type(ident.as_ref()) =>
+            {
+                Some(ident.as_ref().to_string())
+            }
+            _ => None,
+        }
+    }
+
+    fn check_static_member(
+        &mut self,
+        type_name: &str,
+        field: &ast::Field<'input>,
+    ) -> Option<(Vec<ast::Type<'input>>, ast::Type<'input>)> {
+        let member = self.static_member(type_name, field.field.as_ref());
+        match member {
+            Some(_) => {
+                self.annotations.static_members.insert(node_key(field));
+            }
+            None => self.report(
+                Severity::Error,
+                format!(
+                    "{} `{type_name}` has no static member `{}`",
+                    self.type_kind(type_name),
+                    field.field.as_ref()
+                ),
+            ),
+        }
+        member
+    }
+
+    fn check_file(&mut self, prelude: &'a [ast::Decl<'input>], decls: &'a [ast::Decl<'input>]) {
+        let all: Vec<_> = prelude.iter().chain(decls).collect();
+        self.declare(&all);
+        self.declare_bindings(&all);
+
+        // Top level bindings are in scope everywhere, but the types of those without one are
+        // only known once their value is checked
+        let globals = self
+            .top_lets
+            .iter()
+            .map(|(name, let_)| (name.to_string(), let_.let_type.clone()))
+            .chain(
+                self.consts
+                    .iter()
+                    .map(|(name, const_)| (name.to_string(), const_.const_type.clone())),
+            )
+            .collect();
+        self.push_scope(globals);
+
+        for decl in prelude {
+            match decl {
+                ast::Decl::Struct(struct_) => {
+                    self.runtime_types.insert(struct_.name.as_ref());
+                }
+                ast::Decl::Enum(enum_) => {
+                    self.runtime_types.insert(enum_.name.as_ref());
+                }
+                ast::Decl::Func(_)
+                | ast::Decl::Interface(_)
+                | ast::Decl::Impl(_)
+                | ast::Decl::Type(_)
+                | ast::Decl::Let(_)
+                | ast::Decl::Const(_) => {}
+            }
+        }
+
+        for decl in decls {
+            match decl {
+                ast::Decl::Func(func) => {
+                    self.top_item = Some(func.name.as_ref().to_string());
+                    self.check_func(func);
+                }
+                ast::Decl::Struct(struct_) => self.check_struct(struct_),
+                ast::Decl::Enum(enum_) => self.check_enum(enum_),
+                ast::Decl::Interface(interface) => self.check_interface(interface),
+                ast::Decl::Impl(impl_) => self.check_impl(impl_),
+                ast::Decl::Type(type_decl) => self.check_type_decl(type_decl),
+                ast::Decl::Let(let_) => self.check_top_let(let_),
+                ast::Decl::Const(const_) => self.check_const(const_),
+            }
+            self.top_item = None;
+        }
+
+        self.check_init_order(decls);
+    }
+
+    fn check_type_decl(&mut self, type_decl: &ast::TypeDecl<'input>) {
+        self.check_type(&type_decl.target);
+
+        let name = type_decl.name.as_ref();
+        if type_decl.alias && self.alias_refers_to(&type_decl.target, name) {
+            self.report(
+                Severity::Error,
+                format!("type alias `{name}` refers to itself"),
+            );
+        }
+    }
+
+    // Whether the type mentions the alias, directly or through other aliases
+    fn alias_refers_to(&self, type_: &ast::Type<'input>, alias: &str) -> bool {
+        let mut pending = vec![type_];
+        let mut seen = HashSet::new();
+
+        while let Some(type_) = pending.pop() {
+            match type_ {
+                ast::Type::Simple(name) if name.as_ref() == alias => return true,
+                ast::Type::Simple(name) => {
+                    if let Some(target) = self.aliases.get(name.as_ref()) {
+                        if seen.insert(name.as_ref()) {
+                            pending.push(target);
+                        }
+                    }
+                }
+                ast::Type::Generic(_, args) => pending.extend(args),
+            }
+        }
+        false
+    }
+
+    // Replaces aliases with the types they name (an alias that refers to itself is reported where
+    // it is declared, and left as is)
+    fn resolve_alias(&self, type_: &ast::Type<'input>) -> ast::Type<'input> {
+        self.resolve_alias_within(type_, self.aliases.len())
+    }
+
+    fn resolve_alias_within(&self, type_: &ast::Type<'input>, depth: usize) -> ast::Type<'input> {
+        match type_ {
+            ast::Type::Simple(name) => match self.aliases.get(name.as_ref()) {
+                Some(target) if depth > 0 && self.lookup_type_param(name.as_ref()).is_none() => {
+                    self.resolve_alias_within(target, depth - 1)
+                }
+                _ => type_.clone(),
+            },
+            ast::Type::Generic(name, args) => ast::Type::Generic(
+                name.clone(),
+                args.iter()
+                    .map(|arg| self.resolve_alias_within(arg, depth))
+                    .collect(),
+            ),
+        }
+    }
+
+    // Newtypes only mix with values of the same newtype, or with literals (which Go converts)
+    fn mixes_newtypes(
+        &self,
+        type_: &ast::Type<'input>,
+        other: &ast::Type<'input>,
+        exprs: &[&ast::SimpleExpr<'input>],
+    ) -> bool {
+        let (type_, other) = (self.resolve_alias(type_), self.resolve_alias(other));
+        let is_newtype = |type_: &ast::Type<'_>| matches!(type_, ast::Type::Simple(name) if self.newtypes.contains_key(name.as_ref()));
+        let literal = exprs.iter().any(|expr| {
+            matches!(
+                expr,
+                ast::SimpleExpr::IntLit(_)
+                    | ast::SimpleExpr::StringLit(_)
+                    | ast::SimpleExpr::CharLit(_)
+                    | ast::SimpleExpr::BoolLit(_)
+            )
+        });
+
+        (is_newtype(&type_) || is_newtype(&other)) && !literal && !same_type(&type_, &other)
+    }
+
+    fn check_top_let(&mut self, let_: &'a ast::Let<'input>) {
+        let ast::LetTarget::Ident(name) = &let_.target else {
+            return;
+        };
+        self.top_item = Some(name.as_ref().to_string());
+        let type_ = self.check_binding(&let_.let_type, &let_.value);
+        self.bind_global(name.as_ref(), type_);
+    }
+
+    fn check_const(&mut self, const_: &'a ast::Const<'input>) {
+        let name = const_.name.as_ref();
+        self.top_item = Some(name.to_string());
+        let type_ = self.check_binding(&const_.const_type, &const_.value);
+        self.bind_global(name, type_);
+
+        if !self.is_const_value(&const_.value) {
+            self.report(
+                Severity::Error,
+                format!("the value of const `{name}` must be known at compile time"),
+            );
+        }
+    }
+
+    // The type of a top level binding: the declared one, or else that of the value
+    fn check_binding(
+        &mut self,
+        type_: &Option<ast::Type<'input>>,
+        value: &ast::SimpleExpr<'input>,
+    ) -> Option<ast::Type<'input>> {
+        if let Some(type_) = type_ {
+            self.check_type(type_);
+        }
+        let value_type = self.check_simple_expr_as(value, type_.as_ref());
+        type_.clone().or(value_type)
+    }
+
+    fn bind_global(&mut self, name: &str, type_: Option<ast::Type<'input>>) {
+        if let Some(globals) = self.scopes.first_mut() {
+            globals.insert(name.to_string(), type_);
+        }
+    }
+
+    // Literals, other consts, and operators on them - what Go accepts as a constant
+    fn is_const_value(&self, value: &ast::SimpleExpr<'input>) -> bool {
+        match value {
+            ast::SimpleExpr::IntLit(_)
+            | ast::SimpleExpr::StringLit(_)
+            | ast::SimpleExpr::CharLit(_)
+            | ast::SimpleExpr::BoolLit(_) => true,
+            ast::SimpleExpr::Ident(name) => self.consts.contains_key(name.as_ref()),
+            ast::SimpleExpr::BinaryOp(binary_op) => {
+                self.is_const_value(&binary_op.lhs) && self.is_const_value(&binary_op.rhs)
+            }
+            ast::SimpleExpr::BoolCond(bool_cond) => match bool_cond.as_ref() {
+                ast::BoolCond::Not(expr) => self.is_const_value(expr),
+                ast::BoolCond::And(lhs, rhs) | ast::BoolCond::Or(lhs, rhs) => {
+                    self.is_const_value(lhs) && self.is_const_value(rhs)
+                }
+            },
+            ast::SimpleExpr::Expr(expr) => match expr.as_ref() {
+                ast::Expr::Simple(expr) => self.is_const_value(expr),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    // A top level binding can't refer to itself while it is being initialized, whether directly,
+    // through other bindings or through the funcs it calls
+    fn check_init_order(&mut self, decls: &'a [ast::Decl<'input>]) {
+        let mut in_cycle = HashSet::new();
+
+        for decl in decls {
+            let name = match decl {
+                ast::Decl::Let(ast::Let {
+                    target: ast::LetTarget::Ident(name),
+                    ..
+                }) => name.as_ref(),
+                ast::Decl::Const(const_) => const_.name.as_ref(),
+                _ => continue,
+            };
+            if in_cycle.contains(name) {
+                continue;
+            }
+
+            let mut path = vec![name.to_string()];
+            let mut visited = HashSet::new();
+            if self.find_cycle(&mut path, &mut visited) {
+                in_cycle.extend(path.iter().cloned());
+                let path: Vec<_> = path.iter().map(|name| format!("`{name}`")).collect();
+                self.report(
+                    Severity::Error,
+                    format!("initialization cycle: {}", path.join(" refers to ")),
+                );
+            }
+        }
+    }
+
+    // Depth first search for a way from the last item of the path back to the first, which is
+    // left on the path (with the first item again at the end) if found
+    fn find_cycle(&self, path: &mut Vec<String>, visited: &mut HashSet<String>) -> bool {
+        let Some(refs) = path.last().and_then(|item| self.top_refs.get(item)) else {
+            return false;
+        };
+
+        for name in refs {
+            if *name == path[0] {
+                path.push(path[0].clone());
+                return true;
+            }
+
+            if !self.top_refs.contains_key(name) {
+                continue;
+            }
+            if visited.insert(name.clone()) {
+                path.push(name.clone());
+                if self.find_cycle(path, visited) {
+                    return true;
+                }
+                path.pop();
+            }
+        }
+        false
+    }
+
+    fn check_struct(&mut self, struct_: &ast::Struct<'input>) {
+        self.push_type_params(&struct_.type_params);
+        for field in &struct_.fields {
+            self.check_type(&field.field_type);
+        }
+        self.type_params.pop();
+
+        for (idx, field) in struct_.fields.iter().enumerate() {
+            let name = field.name.as_ref();
+            if struct_.fields[..idx]
+                .iter()
+                .any(|prev| prev.name.as_ref() == name)
+            {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "field `{name}` is declared more than once in struct `{}`",
+                        struct_.name.as_ref()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_enum(&mut self, enum_: &ast::Enum<'input>) {
+        let name = enum_.name.as_ref();
+
+        self.push_type_params(&enum_.type_params);
+        for variant in &enum_.variants {
+            for field in &variant.fields {
+                self.check_type(field);
+            }
+        }
+        self.type_params.pop();
+
+        if !enum_.is_c_like() {
+            for variant in &enum_.variants {
+                if variant.value.is_some() {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "variant `{}` of enum `{name}` can't have a value, since the enum has payloads",
+                            variant.name.as_ref()
+                        ),
+                    );
+                }
+            }
+            return;
+        }
+
+        // Without payloads there is nothing to use them for (and the enum is a Go integer)
+        if !enum_.type_params.is_empty() {
+            self.report(
+                Severity::Error,
+                format!("enum `{name}` can't have type parameters, since no variant has a payload"),
+            );
+        }
+
+        // Each variant is a single integer, so a range could only mean several variants
+        for variant in &enum_.variants {
+            if let (Some(start), Some(end)) = (&variant.value, &variant.range_end) {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "variant `{}` of enum `{name}` can't have a range of values ({}..{}): \
+                        give it a single value, and the variants after it count up from there",
+                        variant.name.as_ref(),
+                        start.value,
+                        end.value
+                    ),
+                );
+            }
+        }
+
+        let values = enum_.values();
+        for (idx, value) in values.iter().enumerate() {
+            if let Some(prev) = values[..idx].iter().position(|prev| prev == value) {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "variant `{}` of enum `{name}` has the same value ({value}) as `{}`",
+                        enum_.variants[idx].name.as_ref(),
+                        enum_.variants[prev].name.as_ref()
+                    ),
+                );
+            }
+        }
+    }
+
+    fn check_interface(&mut self, interface: &ast::Interface<'input>) {
+        for (idx, method) in interface.methods.iter().enumerate() {
+            for type_ in method.arg_types.iter().chain(&method.ret_type) {
+                self.check_type(type_);
+            }
+
+            let name = method.name.as_ref();
+            if interface.methods[..idx]
+                .iter()
+                .any(|prev| prev.name.as_ref() == name)
+            {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "method `{name}` is declared more than once in interface `{}`",
+                        interface.name.as_ref()
+                    ),
+                );
+            }
+        }
+    }
+
+    // Every method of the interface must be implemented with the same signature, and nothing else
+    fn check_interface_impl(&mut self, impl_: &ast::Impl<'input>, interface: &ast::Ident<'input>) {
+        let name = impl_.name.as_ref();
+        let Some(interface) = self.interfaces.get(interface.as_ref()).copied() else {
+            self.report(
+                Severity::Error,
+                format!("unknown interface `{}`", interface.as_ref()),
+            );
+            return;
+        };
+        let interface_name = interface.name.as_ref();
+
+        for spec in &interface.methods {
+            let method = spec.name.as_ref();
+            let Some(func) = impl_.funcs.iter().find(|func| func.name.as_ref() == method) else {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "method `{method}` of interface `{interface_name}` is not implemented for `{name}`"
+                    ),
+                );
+                continue;
+            };
+
+            let expected = signature(spec.arg_types.iter(), spec.ret_type.as_ref());
+            let ret_type = return_type(func);
+            let found = signature(func.args.iter().map(|arg| &arg.arg_type), ret_type.as_ref());
+            if self.resolved_signature(spec.arg_types.iter(), spec.ret_type.as_ref())
+                != self.resolved_signature(
+                    func.args.iter().map(|arg| &arg.arg_type),
+                    ret_type.as_ref(),
+                )
+            {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "method `{method}` of `{name}` doesn't match interface `{interface_name}`: expected `{expected}`, found `{found}`"
+                    ),
+                );
+            }
+        }
+
+        for func in &impl_.funcs {
+            let method = func.name.as_ref();
+            if !interface
+                .methods
+                .iter()
+                .any(|spec| spec.name.as_ref() == method)
+            {
+                self.report(
+                    Severity::Error,
+                    format!("method `{method}` is not part of interface `{interface_name}`"),
+                );
+            }
+        }
+    }
+
+    fn check_impl(&mut self, impl_: &'a ast::Impl<'input>) {
+        let name = impl_.name.as_ref();
+        if !self.has_impls(name) {
+            return;
+        }
+        let kind = self.type_kind(name);
+
+        if let Some(interface) = &impl_.interface {
+            self.check_interface_impl(impl_, interface);
+        }
+
+        // The type parameters (and their bounds) are those of the declaration
+        let type_params = self.type_params_of(name).unwrap_or_default();
+        if !impl_
+            .type_params
+            .iter()
+            .map(|param| param.as_ref())
+            .eq(type_params.iter().map(|param| param.name.as_ref()))
+        {
+            if type_params.is_empty() {
+                self.report(
+                    Severity::Error,
+                    format!("{kind} `{name}` has no type parameters, so its impl can't have any"),
+                );
+            } else {
+                let names: Vec<_> = type_params
+                    .iter()
+                    .map(|param| param.name.as_ref())
+                    .collect();
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "impl for {kind} `{name}` must have its type parameters `[{}]`",
+                        names.join(", ")
+                    ),
+                );
+            }
+        }
+        self.type_params.push(
+            type_params
+                .iter()
+                .map(|param| (param.name.as_ref().to_string(), param.bound.clone()))
+                .collect(),
+        );
+
+        let methods = self.methods[name].clone();
+        for func in &impl_.funcs {
+            let method = func.name.as_ref();
+
+            if let Some(struct_) = self.structs.get(name) {
+                if struct_
+                    .fields
+                    .iter()
+                    .any(|field| field.name.as_ref() == method)
+                {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "method `{method}` has the same name as a field of struct `{name}`"
+                        ),
+                    );
+                }
+            }
+            // Enum variants are constructed directly, so there is nothing for 'new' to do
+            if kind == "enum" && method == "new" {
+                self.report(
+                    Severity::Error,
+                    format!("enum `{name}` can't have a `new` function"),
+                );
+            }
+            if method == "str" && self.enums.get(name).is_some_and(|enum_| enum_.is_c_like()) {
+                self.report(
+                    Severity::Error,
+                    format!("method `str` is generated for enum `{name}`, so it can't be declared"),
+                );
+            }
+            // Reported for every declaration after the first
+            let first = methods
+                .iter()
+                .position(|other| other.name.as_ref() == method);
+            if first.is_some_and(|first| !std::ptr::eq(methods[first], func)) {
+                self.report(
+                    Severity::Error,
+                    format!("method `{method}` is declared more than once for {kind} `{name}`"),
+                );
+            }
+
+            // Go methods can't have type parameters of their own
+            if !func.type_params.is_empty() {
+                self.report(
+                    Severity::Error,
+                    format!("method `{method}` of {kind} `{name}` can't have type parameters"),
+                );
+            }
+
+            self.self_type = Some(&impl_.name);
+            self.top_item = Some(format!("{name}.{method}"));
+            self.check_func(func);
+            self.top_item = None;
+            self.self_type = None;
+        }
+
+        self.type_params.pop();
+    }
+
+    fn check_func(&mut self, func: &ast::Func<'input>) {
+        self.funcs.push(func.name.as_ref().to_string());
+        self.check_type_params(func);
+
+        let mut scope = HashMap::with_capacity(func.args.len());
+        for arg in &func.args {
+            self.check_type(&arg.arg_type);
+            scope.insert(arg.name.as_ref().to_string(), Some(arg.arg_type.clone()));
+        }
+        self.push_scope(scope);
+
+        // Default values are copied to the calls, where the args aren't in scope
+        let default_scope = self.default_scope.replace(self.scopes.len() - 1);
+        for arg in &func.args {
+            if let Some(default_val) = &arg.default_val {
+                self.check_simple_expr_as(default_val, Some(&arg.arg_type));
+            }
+        }
+        self.default_scope = default_scope;
+        self.ret_types.push(value_type(func));
+        let in_try = mem::take(&mut self.in_try);
+        let in_async = mem::replace(&mut self.in_async, func.is_async);
+
+        match &func.body {
+            ast::FuncBody::Expr(expr) => {
+                if func.is_async {
+                    self.report(
+                        Severity::Error,
+                        "an `async` func must have a block body".to_string(),
+                    );
+                }
+                self.check_simple_expr(expr);
+            }
+            ast::FuncBody::Block(ret_type, block) => {
+                if let Some(ret_type) = ret_type {
+                    self.check_type(ret_type);
+                }
+                // The statements of a coroutine run as it is resumed, so it has no value
+                let value_type = ret_type
+                    .as_ref()
+                    .filter(|type_| coroutine_types(type_).is_none());
+                self.check_stmts(block, value_type);
+            }
+        }
+
+        self.in_async = in_async;
+        self.in_try = in_try;
+        self.ret_types.pop();
+        self.pop_scope();
+        self.type_params.pop();
+        self.funcs.pop();
+    }
+
+    // There is no syntax to give type arguments at a call, so each one must be inferable from the
+    // arguments
+    fn check_type_params(&mut self, func: &ast::Func<'input>) {
+        self.push_type_params(&func.type_params);
+
+        for param in &func.type_params {
+            let name = param.name.as_ref();
+            if !func.args.iter().any(|arg| mentions(&arg.arg_type, name)) {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "type parameter `{name}` isn't used by any argument, so it can't be inferred"
+                    ),
+                );
+            }
+        }
+    }
+
+    // Brings the type parameters into scope (until the caller pops them)
+    fn push_type_params(&mut self, type_params: &[ast::TypeParam<'input>]) {
+        let mut params = HashMap::with_capacity(type_params.len());
+
+        for param in type_params {
+            let name = param.name.as_ref();
+            if params.contains_key(name) {
+                self.report(
+                    Severity::Error,
+                    format!("type parameter `{name}` is declared more than once"),
+                );
+            } else if self.is_type(name) {
+                self.report(
+                    Severity::Error,
+                    format!("type parameter `{name}` has the same name as a type"),
+                );
+            }
+
+            if let Some(bound) = &param.bound {
+                let bound_name = bound.as_ref();
+                if self.has_impls(bound_name) {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "bound `{bound}` of type parameter `{name}` is a {}, not an interface",
+                            self.type_kind(bound_name)
+                        ),
+                    );
+                } else if !self.interfaces.contains_key(bound_name) {
+                    self.report(Severity::Error, format!("unknown interface `{bound}`"));
+                }
+            }
+            params.insert(name.to_string(), param.bound.clone());
+        }
+
+        self.type_params.push(params);
+    }
+
+    // Type arguments must match the type parameters in number and satisfy their bounds
+    fn check_type(&mut self, type_: &ast::Type<'input>) {
+        match type_ {
+            ast::Type::Simple(name) => {
+                let name = name.as_ref();
+                if let Some(params) = self
+                    .type_params_of(name)
+                    .filter(|params| !params.is_empty())
+                {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "{} `{name}` needs {} type argument(s)",
+                            self.type_kind(name),
+                            params.len()
+                        ),
+                    );
+                }
+            }
+            ast::Type::Generic(name, args) => {
+                for arg in args {
+                    self.check_type(arg);
+                }
+
+                let name = name.as_ref();
+                let (kind, expected) = match (name, self.type_params_of(name)) {
+                    (_, Some(params)) => (self.type_kind(name), params.len()),
+                    _ if self.is_type(name) => (self.type_kind(name), 0),
+                    ("list" | "set" | "iter", None) => ("type", 1),
+                    ("map" | "coroutine", None) => ("type", 2),
+                    _ => return,
+                };
+
+                if expected == 0 {
+                    self.report(
+                        Severity::Error,
+                        format!("{kind} `{name}` doesn't take type arguments"),
+                    );
+                } else if args.len() != expected {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "{kind} `{name}` takes {expected} type argument(s), but {} were given",
+                            args.len()
+                        ),
+                    );
+                } else if let Some(params) = self.type_params_of(name) {
+                    let owner = format!("{kind} `{name}`");
+                    for (param, arg) in params.iter().zip(args) {
+                        self.check_bound(param, arg, &owner);
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_bound(
+        &mut self,
+        param: &ast::TypeParam<'input>,
+        type_: &ast::Type<'input>,
+        owner: &str,
+    ) {
+        let Some(bound) = &param.bound else {
+            return;
+        };
+        let Some(interface) = self.interfaces.get(bound.as_ref()).copied() else {
+            return;
+        };
+
+        if let Some(reason) = self.unsatisfied(type_, interface) {
+            self.report(
+                Severity::Error,
+                format!(
+                    "`{type_}` doesn't implement interface `{bound}` ({reason}), which is required by type parameter `{}` of {owner}",
+                    param.name.as_ref()
+                ),
+            );
+        }
+    }
+
+    // Matches the type of an argument against the declared one, inferring the type parameters it
+    // mentions
+    fn infer<'f>(
+        &mut self,
+        type_params: &'f [ast::TypeParam<'input>],
+        owner: &str,
+        declared: &ast::Type<'input>,
+        actual: &ast::Type<'input>,
+        inferred: &mut HashMap<&'f str, ast::Type<'input>>,
+    ) {
+        match (declared, actual) {
+            (ast::Type::Simple(name), _) => {
+                let Some(param) = type_params
+                    .iter()
+                    .find(|param| param.name.as_ref() == name.as_ref())
+                else {
+                    return;
+                };
+                let name = param.name.as_ref();
+
+                match inferred.get(name) {
+                    Some(prev) if !same_type(prev, actual) => self.report(
+                        Severity::Error,
+                        format!(
+                            "type parameter `{name}` of {owner} can't be both `{prev}` and `{actual}`"
+                        ),
+                    ),
+                    Some(_) => {}
+                    None => {
+                        inferred.insert(name, actual.clone());
+                    }
+                }
+            }
+            (ast::Type::Generic(name, args), ast::Type::Generic(other, other_args))
+                if name.as_ref() == other.as_ref() && args.len() == other_args.len() =>
+            {
+                for (arg, other) in args.iter().zip(other_args) {
+                    self.infer(type_params, owner, arg, other, inferred);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Signature to compare methods by: aliases are the same type as their target, but newtypes
+    // aren't
+    fn resolved_signature<'b>(
+        &self,
+        arg_types: impl Iterator<Item = &'b ast::Type<'input>>,
+        ret_type: Option<&ast::Type<'input>>,
+    ) -> String
+    where
+        'input: 'b,
+    {
+        let arg_types: Vec<_> = arg_types.map(|type_| self.resolve_alias(type_)).collect();
+        let ret_type = ret_type.map(|type_| self.resolve_alias(type_));
+        signature(arg_types.iter(), ret_type.as_ref())
+    }
+
+    // Why the type doesn't satisfy the interface, if it doesn't
+    fn unsatisfied(
+        &self,
+        type_: &ast::Type<'input>,
+        interface: &ast::Interface<'input>,
+    ) -> Option<String> {
+        for spec in &interface.methods {
+            let method = spec.name.as_ref();
+            let (arg_types, ret_type): (Vec<_>, _) = match self.member(type_.as_ref(), method) {
+                Member::Method(func) => (
+                    func.args.iter().map(|arg| &arg.arg_type).collect(),
+                    return_type(func),
+                ),
+                Member::Spec(other) => (other.arg_types.iter().collect(), other.ret_type.clone()),
+                Member::Field(_) | Member::Builtin(_) | Member::Map(_) | Member::Unknown => {
+                    return Some(format!("missing method `{method}`"));
+                }
+            };
+            if self.resolved_signature(spec.arg_types.iter(), spec.ret_type.as_ref())
+                != self.resolved_signature(arg_types.iter().copied(), ret_type.as_ref())
+            {
+                let expected = signature(spec.arg_types.iter(), spec.ret_type.as_ref());
+                let found = signature(arg_types.into_iter(), ret_type.as_ref());
+                return Some(format!(
+                    "method `{method}` is `{found}`, but `{expected}` is expected"
+                ));
+            }
+        }
+        None
+    }
+
+    // Returns the return type with the inferred type arguments. 'args' are the declared argument
+    // each one is given for and its type
+    fn check_generic_call(
+        &mut self,
+        func: &'a ast::Func<'input>,
+        args: &[(Option<usize>, Option<ast::Type<'input>>)],
+    ) -> Option<ast::Type<'input>> {
+        let owner = format!("func `{}`", func.name.as_ref());
+        let mut inferred = HashMap::with_capacity(func.type_params.len());
+
+        for (idx, actual) in args {
+            if let (Some(idx), Some(actual)) = (idx, actual) {
+                self.infer(
+                    &func.type_params,
+                    &owner,
+                    &func.args[*idx].arg_type,
+                    actual,
+                    &mut inferred,
+                );
+            }
+        }
+
+        for param in &func.type_params {
+            if let Some(type_) = inferred.get(param.name.as_ref()) {
+                self.check_bound(param, type_, &owner);
+            }
+        }
+
+        // Type parameters that couldn't be inferred leave the type unknown
+        let ret_type = return_type(func)?;
+        if func.type_params.iter().any(|param| {
+            mentions(&ret_type, param.name.as_ref()) && !inferred.contains_key(param.name.as_ref())
+        }) {
+            return None;
+        }
+        Some(substitute(&ret_type, &inferred))
+    }
+
+    // The type of a value of a struct or enum. The type arguments of a generic one are those of the
+    // type expected (if it's the same type) and inferred from the fields it is given, and are
+    // recorded for the node since Go can't infer them for composite literals
+    fn instantiate<T>(
+        &mut self,
+        node: &T,
+        name: &ast::Ident<'input>,
+        fields: &[(&ast::Type<'input>, Option<ast::Type<'input>>)],
+        expected: Option<&ast::Type<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        let type_name = name.as_ref();
+        let type_params = self.type_params_of(type_name).unwrap_or_default();
+        if type_params.is_empty() {
+            return Some(ast::Type::Simple(name.clone()));
+        }
+
+        let owner = format!("{} `{type_name}`", self.type_kind(type_name));
+        let mut inferred = match expected {
+            Some(expected) if expected.as_ref() == type_name => self.type_args_of(expected),
+            _ => HashMap::with_capacity(type_params.len()),
+        };
+        for (declared, actual) in fields {
+            if let Some(actual) = actual {
+                self.infer(type_params, &owner, declared, actual, &mut inferred);
+            }
+        }
+
+        let mut args = Vec::with_capacity(type_params.len());
+        for param in type_params {
+            let Some(arg) = inferred.get(param.name.as_ref()) else {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "type parameter `{}` of {owner} can't be inferred here",
+                        param.name.as_ref()
+                    ),
+                );
+                return None;
+            };
+            self.check_bound(param, arg, &owner);
+            args.push(arg.clone());
+        }
+
+        self.annotations
+            .type_args
+            .insert(node_key(node), args.iter().map(owned_type).collect());
+        Some(ast::Type::Generic(name.clone(), args))
+    }
+
+    // Records the type arguments of a constructor pattern from the type of the value matched
+    // against. Returns them by type parameter
+    fn instantiate_pattern(
+        &mut self,
+        pattern: &ast::Pattern<'input>,
+        name: &ast::Ident<'input>,
+        type_: Option<&ast::Type<'input>>,
+    ) -> HashMap<&'a str, ast::Type<'input>> {
+        let type_name = name.as_ref();
+        let Some(param) = self
+            .type_params_of(type_name)
+            .and_then(|type_params| type_params.first())
+        else {
+            return HashMap::new();
+        };
+
+        let type_args = match type_ {
+            Some(type_) if type_.as_ref() == type_name => self.type_args_of(type_),
+            _ => HashMap::new(),
+        };
+        match type_ {
+            Some(ast::Type::Generic(_, args)) if !type_args.is_empty() => {
+                self.annotations
+                    .type_args
+                    .insert(node_key(pattern), args.iter().map(owned_type).collect());
+            }
+            _ => self.report(
+                Severity::Error,
+                format!(
+                    "type parameter `{}` of {} `{type_name}` can't be inferred here",
+                    param.name.as_ref(),
+                    self.type_kind(type_name)
+                ),
+            ),
+        }
+        type_args
+    }
+
+    // Checks a block that is a scope of its own
+    fn check_block(&mut self, block: &ast::Block<'input>, result_type: Option<&ast::Type<'input>>) {
+        self.push_scope(HashMap::new());
+        let in_try = mem::take(&mut self.in_try);
+        self.check_stmts(block, result_type);
+        self.in_try = in_try;
+        self.pop_scope();
+    }
+
+    // 'result_type' is the type the last expression is expected to have, if known. Returns the
+    // type of the last expression (or of the name the last '<-' binds), if known
+    fn check_stmts(
+        &mut self,
+        block: &ast::Block<'input>,
+        result_type: Option<&ast::Type<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        let last = block.stmt_or_exprs.len().saturating_sub(1);
+        let mut last_type = None;
+
+        for (idx, stmt_or_expr) in block.stmt_or_exprs.iter().enumerate() {
+            match stmt_or_expr {
+                // Go function literals can't have type parameters
+                ast::StmtOrExpr::Func(func) if !func.type_params.is_empty() => {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "nested func `{}` can't have type parameters",
+                            func.name.as_ref()
+                        ),
+                    );
+                    self.bind_local(func.name.as_ref(), None);
+                    self.check_func(func);
+                }
+                ast::StmtOrExpr::Func(func) => {
+                    // The function can call itself
+                    self.bind_local_func(func);
+                    self.check_func(func);
+                }
+                ast::StmtOrExpr::Let(let_) => self.check_let(let_),
+                ast::StmtOrExpr::Unwrap(unwrap) => {
+                    let type_ = self.check_unwrap(unwrap);
+                    if idx == last {
+                        last_type = type_;
+                    }
+                }
+                // A statement is one of the places 'try' and 'with' can be
+                ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::Try(try_))) => {
+                    let type_ = self.check_try(try_);
+                    if idx == last {
+                        last_type = type_;
+                    }
+                }
+                ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::With(with))) => {
+                    let type_ = self.check_with(with);
+                    if idx == last {
+                        last_type = type_;
+                    }
+                }
+                ast::StmtOrExpr::Expr(ast::Expr::Simple(expr)) if idx == last => {
+                    last_type = self.check_simple_expr_as(expr, result_type);
+                }
+                ast::StmtOrExpr::Expr(ast::Expr::Match(match_)) if idx == last => {
+                    self.check_match(match_, result_type);
+                }
+                ast::StmtOrExpr::Expr(ast::Expr::If(if_)) if idx == last => {
+                    self.check_if(if_, result_type);
+                }
+                ast::StmtOrExpr::Expr(expr) => {
+                    self.check_expr(expr);
+                }
+            }
+        }
+
+        last_type
+    }
+
+    fn check_let(&mut self, let_: &ast::Let<'input>) {
+        match &let_.target {
+            ast::LetTarget::Ident(name) => {
+                if let Some(let_type) = &let_.let_type {
+                    self.check_type(let_type);
+                }
+                // The value of a 'let' is the other place 'try', 'with' and 'match' can be
+                let value_type = match &let_.value {
+                    value if value.as_match().is_some() => value
+                        .as_match()
+                        .and_then(|match_| self.check_match(match_, let_.let_type.as_ref())),
+                    ast::SimpleExpr::Try(try_) => {
+                        if let ast::TryBody::Block(block) = &try_.body {
+                            self.check_block_value(block, "try");
+                        }
+                        self.check_try(try_)
+                    }
+                    ast::SimpleExpr::With(with) => {
+                        self.check_block_value(&with.body, "with");
+                        self.check_with(with)
+                    }
+                    value => self.check_simple_expr_as(value, let_.let_type.as_ref()),
+                };
+                let type_ = let_.let_type.clone().or(value_type);
+
+                // The value of a 'with' or 'match' is assigned inside of a Go block or switch, so
+                // the name is declared before it, which needs the type
+                let assigned = match &let_.value {
+                    ast::SimpleExpr::With(with) => Some((node_key(with.as_ref()), "with")),
+                    value => value.as_match().map(|match_| (node_key(match_), "match")),
+                };
+                if let Some((key, keyword)) = assigned {
+                    match &type_ {
+                        Some(type_) => {
+                            self.annotations.let_types.insert(key, owned_type(type_));
+                        }
+                        None => self.report(
+                            Severity::Error,
+                            format!(
+                                "the type of the `{keyword}` value of `{}` can't be inferred, so \
+                                the `let` needs one",
+                                name.as_ref()
+                            ),
+                        ),
+                    }
+                }
+                self.bind_local(name.as_ref(), type_);
+                if let Some(unread) = self.unread_lets.last_mut() {
+                    unread.insert(name.as_ref().to_string(), node_key(let_));
+                }
+            }
+            ast::LetTarget::SelfField(field) => match self.self_type {
+                Some(type_name) => {
+                    let type_name = type_name.as_ref();
+                    let member = self.member(type_name, field.as_ref());
+                    let field_type = match &member {
+                        Member::Field(field_type) => Some(field_type),
+                        _ => None,
+                    };
+                    self.check_simple_expr_as(&let_.value, field_type);
+
+                    if field_type.is_none() {
+                        self.report(
+                            Severity::Error,
+                            format!(
+                                "{} `{type_name}` has no field `{}`",
+                                self.type_kind(type_name),
+                                field.as_ref()
+                            ),
+                        );
+                    }
+                }
+                None => {
+                    self.check_simple_expr(&let_.value);
+                    self.report(
+                        Severity::Error,
+                        "`self` used outside of an impl".to_string(),
+                    );
+                }
+            },
+        }
+    }
+
+    // Go has nothing like 'try', so it is lowered to statements, which limits it to being a
+    // statement or the value of a 'let'. Returns the type of its value, if known
+    fn check_try(&mut self, try_: &ast::Try<'input>) -> Option<ast::Type<'input>> {
+        let type_ = match &try_.body {
+            ast::TryBody::Expr(expr) => self
+                .check_simple_expr(expr)
+                .and_then(|type_| self.unwrap_type(try_, &type_)),
+            ast::TryBody::Block(block) => {
+                self.push_scope(HashMap::new());
+                let in_try = mem::replace(&mut self.in_try, true);
+                let type_ = self.check_stmts(block, None);
+                self.in_try = in_try;
+                self.pop_scope();
+                type_
+            }
+        };
+
+        self.check_catch(try_);
+        type_
+    }
+
+    // The value of a 'try' or 'with' block is that of its last statement, which must have one
+    fn check_block_value(&mut self, block: &ast::Block<'input>, keyword: &str) {
+        let can_unwrap = keyword == "try";
+
+        match block.stmt_or_exprs.last() {
+            Some(ast::StmtOrExpr::Expr(ast::Expr::Simple(expr)))
+                if !matches!(expr, ast::SimpleExpr::Try(_) | ast::SimpleExpr::With(_)) => {}
+            Some(ast::StmtOrExpr::Unwrap(_)) if can_unwrap => {}
+            _ => self.report(
+                Severity::Error,
+                format!(
+                    "a `{keyword}` block used as a value must end with an expression{}",
+                    if can_unwrap { " or `<-`" } else { "" }
+                ),
+            ),
+        }
+    }
+
+    // The catch block runs when a value can't be unwrapped, and its value is returned from the
+    // function (a coroutine just finishes)
+    fn check_catch(&mut self, try_: &ast::Try<'input>) {
+        let err_name = try_.err_name.as_ref().map_or("err", |name| name.as_ref());
+        let ret_type = self
+            .ret_types
+            .last()
+            .cloned()
+            .flatten()
+            .filter(|type_| coroutine_types(type_).is_none());
+
+        self.push_scope(HashMap::from([(
+            err_name.to_string(),
+            Some(simple_type("error")),
+        )]));
+        let in_try = mem::take(&mut self.in_try);
+        let catch_type = self.check_stmts(&try_.catch_body, ret_type.as_ref());
+        self.in_try = in_try;
+        self.pop_scope();
+
+        // Returning the error from a function that returns a 'Result' wraps it in 'Err'
+        let value_type = ret_type
+            .as_ref()
+            .and_then(|type_| self.result_value_type(type_));
+        if let (Some(catch_type), Some(value_type)) = (catch_type, value_type) {
+            if let Some(ast::StmtOrExpr::Expr(ast::Expr::Simple(expr))) =
+                try_.catch_body.stmt_or_exprs.last()
+            {
+                if same_type(&catch_type, &simple_type("error")) {
+                    self.annotations
+                        .wrapped_errors
+                        .insert(node_key(expr), owned_type(value_type));
+                }
+            }
+        }
+    }
+
+    fn check_unwrap(&mut self, unwrap: &ast::Unwrap<'input>) -> Option<ast::Type<'input>> {
+        if !self.in_try {
+            self.report(
+                Severity::Error,
+                "`<-` can only be used directly in a `try` block".to_string(),
+            );
+        }
+
+        let type_ = self
+            .check_simple_expr(&unwrap.value)
+            .and_then(|type_| self.unwrap_type(unwrap, &type_));
+        self.bind_local(unwrap.name.as_ref(), type_.clone());
+        type_
+    }
+
+    // What '<-' (or 'try' with an expression) unwraps the value to: the 'T' of a 'Result[T]', or
+    // of the 'Result[T]' the '__try__' method of a user defined type returns
+    fn unwrap_type<T>(&mut self, node: &T, type_: &ast::Type<'input>) -> Option<ast::Type<'input>> {
+        if let Some(value_type) = self.result_value_type(type_) {
+            return Some(value_type.clone());
+        }
+
+        let ret_type = match self.member(type_.as_ref(), "__try__") {
+            Member::Method(method) => {
+                return_type(method).map(|ret_type| substitute(&ret_type, &self.type_args_of(type_)))
+            }
+            Member::Spec(spec) => spec.ret_type.clone(),
+            Member::Field(_) | Member::Builtin(_) | Member::Map(_) | Member::Unknown => {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "`{type_}` can't be unwrapped (it isn't a `Result` and has no `__try__` \
+                        method)"
+                    ),
+                );
+                return None;
+            }
+        };
+        self.annotations
+            .magic_methods
+            .insert(node_key(node), "__try__");
+
+        match ret_type
+            .as_ref()
+            .and_then(|ret_type| self.result_value_type(ret_type))
+        {
+            Some(value_type) => Some(value_type.clone()),
+            None => {
+                self.report(
+                    Severity::Error,
+                    format!("`__try__` of `{type_}` must return a `Result`"),
+                );
+                None
+            }
+        }
+    }
+
+    // The context must implement the prelude's 'context' interface. Like 'try', 'with' is lowered
+    // to statements. Returns the type of its value, if known
+    fn check_with(&mut self, with: &ast::With<'input>) -> Option<ast::Type<'input>> {
+        let type_ = self.check_simple_expr(&with.context);
+        if let (Some(type_), Some(context)) = (&type_, self.interfaces.get("context").copied()) {
+            if let Some(reason) = self.unsatisfied(type_, context) {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "`{type_}` can't be used with `with`, as it doesn't implement interface \
+                        `context` ({reason})"
+                    ),
+                );
+            }
+        }
+
+        self.push_scope(HashMap::new());
+        if let Some(name) = &with.name {
+            self.bind_local(name.as_ref(), type_);
+        }
+        let in_try = mem::take(&mut self.in_try);
+        let value_type = self.check_stmts(&with.body, None);
+        self.in_try = in_try;
+        self.pop_scope();
+
+        value_type
+    }
+
+    // The value is handed to whatever resumed the coroutine, and the 'yield' evaluates to the
+    // value it is next resumed with
+    fn check_yield(
+        &mut self,
+        value: Option<&ast::SimpleExpr<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        let types =
+            self.ret_types.last().cloned().flatten().and_then(|type_| {
+                coroutine_types(&type_).map(|(out, in_)| (out.clone(), in_.clone()))
+            });
+
+        match (types, value) {
+            (Some((out, in_)), Some(value)) => {
+                self.check_simple_expr_as(value, Some(&out));
+                Some(in_)
+            }
+            (Some((out, in_)), None) => {
+                if out.as_ref() != "()" {
+                    self.report(
+                        Severity::Error,
+                        format!("`yield` needs a value, as the coroutine yields `{out}`"),
+                    );
+                }
+                Some(in_)
+            }
+            (None, value) => {
+                self.report(
+                    Severity::Error,
+                    "`yield` can only be used in a func returning a `coroutine`".to_string(),
+                );
+                if let Some(value) = value {
+                    self.check_simple_expr(value);
+                }
+                None
+            }
+        }
+    }
+
+    // Waits for the value of a 'Future', which only an 'async' func can do
+    fn check_await(&mut self, future: &ast::SimpleExpr<'input>) -> Option<ast::Type<'input>> {
+        if !self.in_async {
+            self.report(
+                Severity::Error,
+                "`await` can only be used in an `async` func".to_string(),
+            );
+        }
+
+        match self.check_simple_expr(future)? {
+            ast::Type::Generic(name, mut args) if name.as_ref() == "Future" && args.len() == 1 => {
+                args.pop()
+            }
+            type_ => {
+                self.report(
+                    Severity::Error,
+                    format!("only a `Future` can be awaited, not `{type_}`"),
+                );
+                None
+            }
+        }
+    }
+
+    // Go needs to know the type it builds, so it must be inferable. Returns it
+    fn check_comprehension(
+        &mut self,
+        comprehension: &ast::Comprehension<'input>,
+    ) -> Option<ast::Type<'input>> {
+        let (iteration, elem_type) = match self.check_simple_expr(&comprehension.source) {
+            Some(ast::Type::Generic(name, args)) => match (name.as_ref(), args.as_slice()) {
+                ("list", [elem]) => (Some(Iteration::Elements), Some(elem.clone())),
+                ("map", [key, _]) | ("set", [key]) => (Some(Iteration::Keys), Some(key.clone())),
+                ("iter", [elem]) | ("coroutine", [elem, _]) => {
+                    (Some(Iteration::Next), Some(elem.clone()))
+                }
+                _ => (None, None),
+            },
+            _ => (None, None),
+        };
+        if iteration.is_none() {
+            self.report(
+                Severity::Error,
+                "only lists, maps, sets and iterators can be iterated over by a comprehension, \
+                but the type of its source isn't one of them (or can't be inferred)"
+                    .to_string(),
+            );
+        }
+
+        let is_iter = matches!(comprehension.kind, ast::ComprehensionKind::Iter(_));
+        if is_iter {
+            self.iter_locals.push((self.scopes.len(), Vec::new()));
+        }
+        self.push_scope(HashMap::from([(
+            comprehension.name.as_ref().to_string(),
+            elem_type,
+        )]));
+        if let Some(cond) = &comprehension.cond {
+            self.check_cond(cond);
+        }
+
+        let generic = |name, args| ast::Type::Generic(ast::Ident::from_str(name), args);
+        let type_ = match &comprehension.kind {
+            ast::ComprehensionKind::Iter(elem) => self
+                .check_simple_expr(elem)
+                .map(|elem| generic("iter", vec![elem])),
+            ast::ComprehensionKind::List(elem) => self
+                .check_simple_expr(elem)
+                .map(|elem| generic("list", vec![elem])),
+            ast::ComprehensionKind::Map(key, value) => {
+                let key = self.check_simple_expr(key);
+                let value = self.check_simple_expr(value);
+                key.zip(value)
+                    .map(|(key, value)| generic("map", vec![key, value]))
+            }
+            ast::ComprehensionKind::Set(elem) => self
+                .check_simple_expr(elem)
+                .map(|elem| generic("set", vec![elem])),
+        };
+        self.pop_scope();
+        if is_iter {
+            self.check_iter_struct(comprehension);
+        }
+
+        match (iteration, &type_) {
+            (Some(iteration), Some(type_)) => {
+                self.annotations
+                    .comprehensions
+                    .insert(node_key(comprehension), (iteration, owned_type(type_)));
+            }
+            (Some(_), None) => self.report(
+                Severity::Error,
+                "the type of the elements of the comprehension can't be inferred".to_string(),
+            ),
+            (None, _) => {}
+        }
+        type_
+    }
+
+    // The struct fields need the types of the locals the iterator comprehension refers to
+    fn check_iter_struct(&mut self, comprehension: &ast::Comprehension<'input>) {
+        let Some((_, locals)) = self.iter_locals.pop() else {
+            return;
+        };
+
+        let mut iter_struct = IterStruct::default();
+        for (name, type_) in locals {
+            match type_ {
+                Some(type_) => iter_struct.locals.push((name, owned_type(&type_))),
+                None => self.report(
+                    Severity::Error,
+                    format!(
+                        "the type of `{name}` can't be inferred, so an iterator comprehension \
+                        can't refer to it"
+                    ),
+                ),
+            }
+        }
+
+        let mut type_params: Vec<_> = self
+            .type_params
+            .iter()
+            .flat_map(|params| params.iter())
+            .map(|(name, bound)| ast::TypeParam {
+                name: ast::Ident::from_string(name.clone()),
+                bound: bound.as_ref().map(owned_type),
+            })
+            .collect();
+        type_params.sort_by(|param, other| param.name.as_ref().cmp(other.name.as_ref()));
+        type_params.dedup_by(|param, other| param.name.as_ref() == other.name.as_ref());
+        iter_struct.type_params = type_params;
+
+        self.annotations
+            .iter_structs
+            .insert(node_key(comprehension), iter_struct);
+    }
+
+    // 'T' if the type is the prelude's 'Result[T]'
+    fn result_value_type<'t>(&self, type_: &'t ast::Type<'input>) -> Option<&'t ast::Type<'input>> {
+        match type_ {
+            ast::Type::Generic(name, args)
+                if name.as_ref() == "Result"
+                    && args.len() == 1
+                    && self.runtime_types.contains("Result") =>
+            {
+                Some(&args[0])
+            }
+            _ => None,
+        }
+    }
+
+    // Adds the names bound by the pattern to the current scope. 'type_' is the type of the
+    // value matched against, if known
+    fn bind_pattern(&mut self, pattern: &ast::Pattern<'input>, type_: Option<ast::Type<'input>>) {
+        match pattern {
+            ast::Pattern::Wildcard
+            | ast::Pattern::IntLit(_)
+            | ast::Pattern::StringLit(_)
+            | ast::Pattern::CharLit(_)
+            | ast::Pattern::BoolLit(_) => {}
+            ast::Pattern::Bind(name) => {
+                // A bare variant name is a test, not a binding
+                match self.variants.get(name.as_ref()).copied() {
+                    Some((enum_, variant)) if variant.fields.is_empty() => {
+                        self.instantiate_pattern(pattern, &enum_.name, type_.as_ref());
+                    }
+                    _ => self.bind_local(name.as_ref(), type_),
+                }
+            }
+            ast::Pattern::Alt(patterns) => {
+                for pattern in patterns {
+                    self.bind_pattern(pattern, type_.clone());
+                }
+            }
+            ast::Pattern::As(pattern, name) => {
+                self.bind_local(name.as_ref(), type_.clone());
+                self.bind_pattern(pattern, type_);
+            }
+            ast::Pattern::Type(type_pattern) => {
+                self.check_type(&type_pattern.pattern_type);
+                if let Some(name) = &type_pattern.name {
+                    self.bind_local(name.as_ref(), Some(type_pattern.pattern_type.clone()));
+                }
+            }
+            ast::Pattern::Ctor(ctor_pattern) => {
+                let name = ctor_pattern.name.as_ref();
+
+                let (kind, owner, fields): (_, _, Vec<_>) = match (
+                    self.structs.get(name).copied(),
+                    self.variants.get(name).copied(),
+                ) {
+                    (Some(struct_), _) => (
+                        "struct",
+                        &struct_.name,
+                        struct_
+                            .fields
+                            .iter()
+                            .map(|field| &field.field_type)
+                            .collect(),
+                    ),
+                    (None, Some((enum_, variant))) => {
+                        ("variant", &enum_.name, variant.fields.iter().collect())
+                    }
+                    (None, None) => {
+                        for arg in &ctor_pattern.args {
+                            self.bind_pattern(arg, None);
+                        }
+                        return;
+                    }
+                };
+
+                if fields.len() != ctor_pattern.args.len() {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "{kind} `{name}` has {} field(s), but the pattern has {}",
+                            fields.len(),
+                            ctor_pattern.args.len()
+                        ),
+                    );
+                }
+
+                let type_args = self.instantiate_pattern(pattern, owner, type_.as_ref());
+                for (field_type, arg) in fields.into_iter().zip(&ctor_pattern.args) {
+                    self.bind_pattern(arg, Some(substitute(field_type, &type_args)));
+                }
+            }
+        }
+    }
+
+    // 'expected' is the type the arms are expected to have, if known
+    // Returns the type of the arms, if they all have the same known type
+    fn check_match(
+        &mut self,
+        match_: &ast::Match<'input>,
+        expected: Option<&ast::Type<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        let type_ = self.check_simple_expr(&match_.expr);
+        let mut bad_patterns = false;
+        let mut arm_types = Vec::with_capacity(match_.arms.len());
+        for arm in &match_.arms {
+            self.push_scope(HashMap::new());
+            let errors = self.diagnostics.len();
+            self.bind_pattern(&arm.pattern, type_.clone());
+            bad_patterns |= self.diagnostics.len() > errors;
+            // An arm's value can be another 'match', which is lowered along with this one
+            let arm_type = match arm.body.as_match() {
+                Some(inner) => self.check_match(inner, expected),
+                None => self.check_simple_expr_as(&arm.body, expected),
+            };
+            arm_types.push(arm_type);
+            self.pop_scope();
+        }
+
+        let value_type = match arm_types.split_first() {
+            Some((Some(first), rest))
+                if rest
+                    .iter()
+                    .all(|type_| type_.as_ref().is_some_and(|type_| same_type(type_, first))) =>
+            {
+                Some(first.clone())
+            }
+            _ => None,
+        };
+
+        // Don't pile more errors on patterns that are already wrong
+        if bad_patterns {
+            return value_type;
+        }
+
+        let report = self.exhaustiveness.check(&match_.arms);
+
+        for pattern in report.unreachable {
+            self.report(
+                Severity::Warning,
+                format!("unreachable match pattern `{pattern}`"),
+            );
+        }
+
+        if !report.missing.is_empty() {
+            let mut missing = report
+                .missing
+                .iter()
+                .map(|pattern| format!("`{pattern}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if report.more_missing {
+                missing.push_str(" and more");
+            }
+
+            self.report(
+                Severity::Error,
+                format!("non-exhaustive match: {missing} not covered"),
+            );
+        }
+        value_type
+    }
+
+    // 'result_type' is the type the last expression of each block is expected to have, if known
+    fn check_if(&mut self, if_: &ast::If<'input>, result_type: Option<&ast::Type<'input>>) {
+        self.check_cond(&if_.cond);
+        self.check_block(&if_.then_body, result_type);
+
+        match &if_.else_body {
+            Some(ast::ElseBody::If(else_if)) => self.check_if(else_if, result_type),
+            Some(ast::ElseBody::Block(block)) => self.check_block(block, result_type),
+            None => {}
+        }
+    }
+
+    // Returns the type of the expression, if known
+    fn check_expr(&mut self, expr: &ast::Expr<'input>) -> Option<ast::Type<'input>> {
+        match expr {
+            ast::Expr::If(if_) => {
+                self.check_if(if_, None);
+                None
+            }
+            ast::Expr::Match(match_) => {
+                self.check_match(match_, None);
+                None
+            }
+            ast::Expr::Simple(simple_expr) => self.check_simple_expr(simple_expr),
+        }
+    }
+
+    // Returns the member the field refers to (if the target's type is known), and the type
+    // arguments of the target (so the types of a generic type's members can be substituted). The
+    // type of a field is already substituted
+    fn check_field(
+        &mut self,
+        field: &ast::Field<'input>,
+    ) -> (Member<'a, 'input>, HashMap<&'a str, ast::Type<'input>>) {
+        if let Some(type_name) = self.static_target(field) {
+            let member = match self.check_static_member(&type_name, field) {
+                Some((_, ret_type)) => Member::Builtin(ret_type),
+                None => Member::Unknown,
+            };
+            return (member, HashMap::new());
+        }
+
+        let Some(target_type) = self.check_simple_expr(&field.target) else {
+            return (Member::Unknown, HashMap::new());
+        };
+        let type_name = target_type.as_ref();
+        if !self.is_type(type_name) {
+            return (Member::Unknown, HashMap::new());
+        }
+
+        let type_args = self.type_args_of(&target_type);
+        let member = match self.member(type_name, field.field.as_ref()) {
+            // The runtime's fields aren't exported
+            Member::Field(_) if self.runtime_types.contains(type_name) => {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "field `{}` of struct `{type_name}` belongs to the runtime, so it can't be used",
+                        field.field.as_ref()
+                    ),
+                );
+                return (Member::Unknown, type_args);
+            }
+            Member::Field(field_type) => Member::Field(substitute(&field_type, &type_args)),
+            Member::Method(method) if self.runtime_types.contains(type_name) => {
+                self.annotations.runtime_methods.insert(node_key(field));
+                Member::Method(method)
+            }
+            member => member,
+        };
+        if let Member::Unknown = member {
+            self.report(
+                Severity::Error,
+                format!(
+                    "{} `{type_name}` has no field or method `{}`",
+                    self.type_kind(type_name),
+                    field.field.as_ref()
+                ),
+            );
+        }
+        (member, type_args)
+    }
+
+    // There are no function types, so 'map' is given the name of a func, which must take the
+    // value the 'Option' or 'Result' holds. Returns the type holding what the func returns
+    fn check_map(
+        &mut self,
+        map_func: &'static str,
+        call: &ast::Call<'input>,
+        type_args: &HashMap<&'a str, ast::Type<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        self.check_args(&call.args, &[], &[]);
+        let type_name = if map_func == "MapOption" {
+            "Option"
+        } else {
+            "Result"
+        };
+
+        let func_name = match call.args.as_slice() {
+            [ast::CallArg {
+                name: None,
+                expr: ast::SimpleExpr::Ident(ident),
+            }] => ident.as_ref(),
+            _ => {
+                self.report(
+                    Severity::Error,
+                    format!("`map` of `{type_name}` takes the name of a func"),
+                );
+                return None;
+            }
+        };
+        let (arg_types, ret_type) = match self.lookup_local_func(func_name) {
+            Some(func) => (func.arg_types.clone(), func.ret_type.clone()),
+            None if self.lookup_local(func_name).is_some() => {
+                self.report(
+                    Severity::Error,
+                    format!("`map` of `{type_name}` takes the name of a func"),
+                );
+                return None;
+            }
+            None => match self.top_funcs.get(func_name).copied() {
+                Some(func) if func.type_params.is_empty() => (
+                    func.args.iter().map(|arg| arg.arg_type.clone()).collect(),
+                    return_type(func),
+                ),
+                Some(_) => {
+                    self.report(
+                        Severity::Error,
+                        format!("generic func `{func_name}` can't be given to `map`"),
+                    );
+                    return None;
+                }
+                None => {
+                    self.report(
+                        Severity::Error,
+                        format!("`map` of `{type_name}` takes the name of a func"),
+                    );
+                    return None;
+                }
+            },
+        };
+
+        let value_type = type_args.get("T");
+        match (arg_types.as_slice(), value_type) {
+            ([arg_type], Some(value_type)) if !same_type(arg_type, value_type) => {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "func `{func_name}` given to `map` takes a `{arg_type}`, but the `{type_name}` holds a `{value_type}`"
+                    ),
+                );
+            }
+            ([_], _) => {}
+            _ => self.report(
+                Severity::Error,
+                format!("func `{func_name}` given to `map` must take 1 argument"),
+            ),
+        }
+
+        // The type of the 'Option' or 'Result' returned is what the func is declared to return
+        let Some(ret_type) = ret_type else {
+            self.report(
+                Severity::Error,
+                format!("func `{func_name}` given to `map` must declare its return type"),
+            );
+            return None;
+        };
+
+        self.annotations.map_funcs.insert(node_key(call), map_func);
+        Some(ast::Type::Generic(
+            ast::Ident::from_str(type_name),
+            vec![ret_type],
+        ))
+    }
+
+    // Field names are matched to the named args, and then in order to the positional args
+    fn check_constructor(&mut self, struct_: &ast::Struct<'input>, call: &ast::Call<'input>) {
+        let name = struct_.name.as_ref();
+        let mut set = vec![false; struct_.fields.len()];
+        let mut next_pos = 0;
+
+        for arg in &call.args {
+            let idx = match &arg.name {
+                Some(arg_name) => {
+                    let idx = struct_
+                        .fields
+                        .iter()
+                        .position(|field| field.name.as_ref() == arg_name.as_ref());
+                    if idx.is_none() {
+                        self.report(
+                            Severity::Error,
+                            format!("struct `{name}` has no field `{}`", arg_name.as_ref()),
+                        );
+                    }
+                    idx
+                }
+                None => {
+                    next_pos += 1;
+                    if next_pos > struct_.fields.len() {
+                        self.report(
+                            Severity::Error,
+                            format!("too many arguments to constructor of struct `{name}`"),
+                        );
+                        None
+                    } else {
+                        Some(next_pos - 1)
+                    }
+                }
+            };
+
+            if let Some(idx) = idx {
+                if set[idx] {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "field `{}` given more than once to constructor of struct `{name}`",
+                            struct_.fields[idx].name.as_ref()
+                        ),
+                    );
+                }
+                set[idx] = true;
+            }
+        }
+
+        for (field, set) in struct_.fields.iter().zip(set) {
+            if !set {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "missing field `{}` in constructor of struct `{name}`",
+                        field.name.as_ref()
+                    ),
+                );
+            }
+        }
+    }
+
+    // Matches the args of a call to those of the func, which are given by position and then by
+    // name, or else take their default value
+    fn check_call_args(
+        &mut self,
+        name: &str,
+        params: &[(&str, Option<usize>)],
+        call: &ast::Call<'input>,
+    ) {
+        let mut values = vec![None; params.len()];
+        let mut next_pos = 0;
+        let mut valid = true;
+
+        for (arg_idx, arg) in call.args.iter().enumerate() {
+            let idx = match &arg.name {
+                Some(arg_name) => {
+                    let idx = params
+                        .iter()
+                        .position(|(param, _)| *param == arg_name.as_ref());
+                    if idx.is_none() {
+                        self.report(
+                            Severity::Error,
+                            format!("func `{name}` has no argument `{}`", arg_name.as_ref()),
+                        );
+                    }
+                    idx
+                }
+                None => {
+                    next_pos += 1;
+                    if next_pos > params.len() {
+                        self.report(
+                            Severity::Error,
+                            format!("too many arguments in call to func `{name}`"),
+                        );
+                        None
+                    } else {
+                        Some(next_pos - 1)
+                    }
+                }
+            };
+
+            match idx {
+                Some(idx) if values[idx].is_some() => {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "argument `{}` given more than once in call to func `{name}`",
+                            params[idx].0
+                        ),
+                    );
+                    valid = false;
+                }
+                Some(idx) => values[idx] = Some(ArgValue::Given(arg_idx)),
+                None => valid = false,
+            }
+        }
+
+        let values: Vec<_> = params
+            .iter()
+            .zip(values)
+            .filter_map(
+                |(&(param, default_val), value)| match (value, default_val) {
+                    (Some(value), _) => Some(value),
+                    (None, Some(default_val)) => Some(ArgValue::Default(default_val)),
+                    (None, None) => {
+                        self.report(
+                            Severity::Error,
+                            format!("missing argument `{param}` in call to func `{name}`"),
+                        );
+                        valid = false;
+                        None
+                    }
+                },
+            )
+            .collect();
+
+        if valid {
+            self.annotations.call_args.insert(node_key(call), values);
+        }
+    }
+
+    // Returns the return type of the magic method (if known), or None if the type doesn't have it
+    // (which is reported as 'what')
+    fn check_magic(
+        &mut self,
+        type_name: &str,
+        method: &str,
+        what: &str,
+    ) -> Option<Option<ast::Type<'input>>> {
+        match self.member(type_name, method) {
+            Member::Method(func) => Some(return_type(func)),
+            Member::Spec(spec) => Some(spec.ret_type.clone()),
+            Member::Field(_) | Member::Builtin(_) | Member::Map(_) | Member::Unknown => {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "{} `{type_name}` {what} (it has no `{method}` method)",
+                        self.type_kind(type_name)
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    // Conditions are "truthy": anything that isn't a bool is converted to one. Conditions of
+    // unknown type are left as is
+    fn check_cond(&mut self, cond: &ast::SimpleExpr<'input>) {
+        let Some(type_) = self.check_simple_expr(cond) else {
+            return;
+        };
+
+        let truthiness = match canonical_type(type_.as_ref()) {
+            "bool" => None,
+            "int" | "char" => Some(Truthiness::NonZero),
+            "str" | "list" | "map" | "set" => Some(Truthiness::NonEmpty),
+            name if self.is_type(name) => self.user_truthiness(name),
+            _ => None,
+        };
+        if let Some(truthiness) = truthiness {
+            // The 'None' compared against needs the type arguments
+            if let (Truthiness::NotNone, ast::Type::Generic(_, args)) = (truthiness, &type_) {
+                self.annotations
+                    .type_args
+                    .insert(node_key(cond), args.iter().map(owned_type).collect());
+            }
+            self.annotations
+                .conditions
+                .insert(node_key(cond), truthiness);
+        }
+    }
+
+    // '__bool__' wins over the 'None' rule, so an enum can define its own truthiness
+    fn user_truthiness(&mut self, name: &str) -> Option<Truthiness> {
+        if let Member::Method(_) | Member::Spec(_) = self.member(name, "__bool__") {
+            return Some(Truthiness::Method);
+        }
+
+        let has_none = self.enums.get(name).is_some_and(|enum_| {
+            enum_
+                .variants
+                .iter()
+                .any(|variant| variant.name.as_ref() == "None" && variant.fields.is_empty())
+        });
+        if has_none {
+            Some(Truthiness::NotNone)
+        } else {
+            self.check_magic(name, "__bool__", "can't be used as a condition")
+                .map(|_| Truthiness::Method)
+        }
+    }
+
+    fn check_binary_op(&mut self, binary_op: &ast::BinaryOp<'input>) -> Option<ast::Type<'input>> {
+        let lhs = self.check_simple_expr(&binary_op.lhs);
+        let rhs = self.check_simple_expr_as(&binary_op.rhs, lhs.as_ref());
+        if let (Some(lhs), Some(rhs)) = (&lhs, &rhs) {
+            if self.mixes_newtypes(lhs, rhs, &[&binary_op.lhs, &binary_op.rhs]) {
+                self.report(
+                    Severity::Error,
+                    format!(
+                        "mismatched types `{lhs}` and `{rhs}` in `{}`",
+                        binary_op.op.symbol()
+                    ),
+                );
+            }
+        }
+        let comparison = matches!(
+            binary_op.op,
+            ast::BinaryOperator::Equal | ast::BinaryOperator::NotEqual
+        );
+
+        match lhs {
+            Some(type_) if self.is_type(type_.as_ref()) => {
+                let method = binary_op.op.magic_method();
+                let ret_type = self.check_magic(
+                    type_.as_ref(),
+                    method,
+                    &format!("doesn't support `{}`", binary_op.op.symbol()),
+                )?;
+                self.annotations
+                    .magic_methods
+                    .insert(node_key(binary_op), method);
+
+                if comparison {
+                    Some(simple_type("bool"))
+                } else {
+                    ret_type
+                }
+            }
+            // Built in types use the Go operators
+            _ if comparison => Some(simple_type("bool")),
+            Some(type_) if rhs.as_ref().is_some_and(|rhs| same_type(rhs, &type_)) => Some(type_),
+            _ => None,
+        }
+    }
+
+    fn check_index(&mut self, index: &ast::Index<'input>) -> Option<ast::Type<'input>> {
+        let target = self.check_simple_expr(&index.target);
+        for expr in &index.indexes {
+            self.check_simple_expr(expr);
+        }
+
+        match target {
+            Some(type_) if self.is_type(type_.as_ref()) => {
+                let ret_type = self.check_magic(type_.as_ref(), "__index__", "can't be indexed")?;
+                self.annotations
+                    .magic_methods
+                    .insert(node_key(index), "__index__");
+                ret_type
+            }
+            target => {
+                if index.indexes.len() > 1 {
+                    self.report(
+                        Severity::Error,
+                        format!(
+                            "only types with an `__index__` method can take more than one index, \
+                            but {} were given",
+                            index.indexes.len()
+                        ),
+                    );
+                }
+
+                match target {
+                    Some(ast::Type::Generic(name, args)) => {
+                        match (name.as_ref(), args.as_slice()) {
+                            ("list", [elem]) => Some(elem.clone()),
+                            ("map", [_, value]) => Some(value.clone()),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    // Variant payloads are positional only
+    fn check_variant_args(
+        &mut self,
+        variant: &ast::Variant<'input>,
+        args: &[ast::CallArg<'input>],
+    ) {
+        let name = variant.name.as_ref();
+
+        if args.iter().any(|arg| arg.name.is_some()) {
+            self.report(
+                Severity::Error,
+                format!("variant `{name}` doesn't take named arguments"),
+            );
+        }
+        if args.len() != variant.fields.len() {
+            self.report(
+                Severity::Error,
+                format!(
+                    "variant `{name}` takes {} argument(s), but {} were given",
+                    variant.fields.len(),
+                    args.len()
+                ),
+            );
+        }
+    }
+
+    // Checks each argument against the type expected for the argument (or field) it is given
+    // for, which is found by name for named arguments and by position otherwise. Returns the
+    // index of that argument (if any) and the type of each
+    fn check_args(
+        &mut self,
+        args: &[ast::CallArg<'input>],
+        names: &[&str],
+        expected: &[Option<ast::Type<'input>>],
+    ) -> Vec<(Option<usize>, Option<ast::Type<'input>>)> {
+        let mut positional = 0..names.len();
+
+        args.iter()
+            .map(|arg| {
+                let idx = match &arg.name {
+                    Some(name) => names.iter().position(|other| *other == name.as_ref()),
+                    None => positional.next(),
+                };
+                let expected = idx.and_then(|idx| expected[idx].as_ref());
+                let type_ = self.check_simple_expr_as(&arg.expr, expected);
+
+                if let (Some(expected), Some(type_)) = (expected, &type_) {
+                    if self.mixes_newtypes(expected, type_, &[&arg.expr]) {
+                        self.report(
+                            Severity::Error,
+                            format!("expected a `{expected}` argument, but a `{type_}` was given"),
+                        );
+                    }
+                }
+                (idx, type_)
+            })
+            .collect()
+    }
+
+    fn check_call(
+        &mut self,
+        call: &ast::Call<'input>,
+        expected: Option<&ast::Type<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        match &call.target {
+            ast::SimpleExpr::Ident(ident) if self.lookup_local(ident.as_ref()).is_none() => {
+                let name = ident.as_ref();
+
+                if let Some(struct_) = self.structs.get(name).copied() {
+                    if self.runtime_types.contains(name) {
+                        self.report(
+                            Severity::Error,
+                            format!("struct `{name}` belongs to the runtime, so it can't be constructed"),
+                        );
+                    }
+                    // With a 'new' function, the args are passed to it instead
+                    let new = self.methods.get(name).and_then(|methods| {
+                        methods
+                            .iter()
+                            .find(|func| func.name.as_ref() == "new")
+                            .copied()
+                    });
+                    let (names, declared): (Vec<_>, Vec<_>) = match new {
+                        Some(new) => new
+                            .args
+                            .iter()
+                            .map(|arg| (arg.name.as_ref(), &arg.arg_type))
+                            .unzip(),
+                        None => struct_
+                            .fields
+                            .iter()
+                            .map(|field| (field.name.as_ref(), &field.field_type))
+                            .unzip(),
+                    };
+
+                    let args = self.check_instance_args(call, &names, &declared, expected);
+                    match new {
+                        Some(new) => {
+                            self.refer_method(new);
+                            self.check_call_args("new", &call_params(new), call);
+                        }
+                        None => self.check_constructor(struct_, call),
+                    }
+                    self.instantiate(call, &struct_.name, &args, expected)
+                } else if let Some((enum_, variant)) = self.variants.get(name).copied() {
+                    let declared: Vec<_> = variant.fields.iter().collect();
+                    let args = self.check_instance_args(call, &[], &declared, expected);
+                    self.check_variant_args(variant, &call.args);
+                    self.instantiate(call, &enum_.name, &args, expected)
+                } else if let Some(target) = self.newtypes.get(name).copied() {
+                    self.check_args(&call.args, &[""], &[Some(target.clone())]);
+                    if call.args.iter().any(|arg| arg.name.is_some()) {
+                        self.report(
+                            Severity::Error,
+                            format!("newtype `{name}` doesn't take named arguments"),
+                        );
+                    }
+                    if call.args.len() != 1 {
+                        self.report(
+                            Severity::Error,
+                            format!(
+                                "newtype `{name}` takes 1 argument, but {} were given",
+                                call.args.len()
+                            ),
+                        );
+                    }
+                    Some(ast::Type::Simple(ident.clone()))
+                } else {
+                    match self.top_funcs.get(name).copied() {
+                        Some(func) => {
+                            self.refer_top(name);
+                            let names: Vec<_> =
+                                func.args.iter().map(|arg| arg.name.as_ref()).collect();
+                            // The types of the type parameters aren't known until they're inferred
+                            let expected: Vec<_> =
+                                func.args
+                                    .iter()
+                                    .map(|arg| {
+                                        let generic = func.type_params.iter().any(|param| {
+                                            mentions(&arg.arg_type, param.name.as_ref())
+                                        });
+                                        (!generic).then(|| arg.arg_type.clone())
+                                    })
+                                    .collect();
+                            let args = self.check_args(&call.args, &names, &expected);
+                            self.check_call_args(name, &call_params(func), call);
+
+                            if func.type_params.is_empty() {
+                                return_type(func)
+                            } else {
+                                self.check_generic_call(func, &args)
+                            }
+                        }
+                        None => {
+                            self.check_args(&call.args, &[], &[]);
+                            None
+                        }
+                    }
+                }
+            }
+            ast::SimpleExpr::Field(field) if self.static_target(field).is_some() => {
+                let type_name = self.static_target(field)?;
+                match self.check_static_member(&type_name, field) {
+                    Some((arg_types, ret_type)) => {
+                        let expected: Vec<_> = arg_types.into_iter().map(Some).collect();
+                        self.check_args(&call.args, &vec![""; expected.len()], &expected);
+                        Some(ret_type)
+                    }
+                    None => {
+                        self.check_args(&call.args, &[], &[]);
+                        None
+                    }
+                }
+            }
+            ast::SimpleExpr::Field(field) => {
+                let (member, type_args) = self.check_field(field);
+
+                match member {
+                    Member::Method(method) => {
+                        let (names, expected): (Vec<_>, Vec<_>) = method
+                            .args
+                            .iter()
+                            .map(|arg| {
+                                (
+                                    arg.name.as_ref(),
+                                    Some(substitute(&arg.arg_type, &type_args)),
+                                )
+                            })
+                            .unzip();
+                        self.check_args(&call.args, &names, &expected);
+                        self.check_call_args(method.name.as_ref(), &call_params(method), call);
+                        self.refer_method(method);
+                        return_type(method).map(|ret_type| substitute(&ret_type, &type_args))
+                    }
+                    Member::Spec(spec) => {
+                        let expected: Vec<_> = spec.arg_types.iter().cloned().map(Some).collect();
+                        self.check_args(&call.args, &vec![""; expected.len()], &expected);
+                        spec.ret_type.clone()
+                    }
+                    Member::Builtin(type_) => {
+                        self.check_args(&call.args, &[], &[]);
+                        Some(type_)
+                    }
+                    Member::Map(map_func) => self.check_map(map_func, call, &type_args),
+                    Member::Field(_) | Member::Unknown => {
+                        self.check_args(&call.args, &[], &[]);
+                        None
+                    }
+                }
+            }
+            ast::SimpleExpr::Ident(ident) if self.lookup_local_func(ident.as_ref()).is_some() => {
+                self.check_simple_expr(&call.target);
+                let func = self.lookup_local_func(ident.as_ref())?;
+                let (name, ret_type) = (func.name.clone(), func.ret_type.clone());
+                let params = func.params.clone();
+                let expected: Vec<_> = func.arg_types.iter().cloned().map(Some).collect();
+
+                let params: Vec<_> = params
+                    .iter()
+                    .map(|(param, default_val)| (param.as_str(), *default_val))
+                    .collect();
+                let names: Vec<_> = params.iter().map(|(param, _)| *param).collect();
+                self.check_args(&call.args, &names, &expected);
+                self.check_call_args(&name, &params, call);
+                ret_type
+            }
+            target => {
+                self.check_simple_expr(target);
+                self.check_args(&call.args, &[], &[]);
+                None
+            }
+        }
+    }
+
+    // The args of a struct or variant construction, which are expected to have the declared
+    // types, with the type arguments of the expected type (if it is an instance of the same
+    // generic type) substituted in. Returns the declared type of each with the type of the arg
+    fn check_instance_args<'d>(
+        &mut self,
+        call: &ast::Call<'input>,
+        names: &[&str],
+        declared: &[&'d ast::Type<'input>],
+        expected: Option<&ast::Type<'input>>,
+    ) -> Vec<(&'d ast::Type<'input>, Option<ast::Type<'input>>)> {
+        let type_params = match &call.target {
+            ast::SimpleExpr::Ident(name) => match self.variants.get(name.as_ref()).copied() {
+                Some((enum_, _)) => &enum_.type_params,
+                None => self.type_params_of(name.as_ref()).unwrap_or_default(),
+            },
+            _ => &[],
+        };
+        let type_args = expected
+            .map(|type_| self.type_args_of(type_))
+            .unwrap_or_default();
+
+        let expected: Vec<_> = declared
+            .iter()
+            .map(|type_| {
+                let type_ = substitute(type_, &type_args);
+                let generic = type_params
+                    .iter()
+                    .any(|param| mentions(&type_, param.name.as_ref()));
+                (!generic).then_some(type_)
+            })
+            .collect();
+        // Variants have only positional args
+        let names = if names.is_empty() {
+            &vec![""; declared.len()][..]
+        } else {
+            names
+        };
+
+        self.check_args(&call.args, names, &expected)
+            .into_iter()
+            .filter_map(|(idx, type_)| Some((declared[idx?], type_)))
+            .collect()
+    }
+
+    // Like 'check_simple_expr', but given the type the value is expected to have (if known), which
+    // is where the type arguments of a generic type that the value doesn't mention come from
+    fn check_simple_expr_as(
+        &mut self,
+        expr: &ast::SimpleExpr<'input>,
+        expected: Option<&ast::Type<'input>>,
+    ) -> Option<ast::Type<'input>> {
+        // Values never have the type of an alias, only the type it names
+        let expected = expected.map(|type_| self.resolve_alias(type_));
+        let expected = expected.as_ref();
+
+        let type_ = match expr {
+            ast::SimpleExpr::Ident(ident) => match self.lookup_variant(ident.as_ref()) {
+                Some((enum_, variant)) => {
+                    if !variant.fields.is_empty() {
+                        self.check_variant_args(variant, &[]);
+                        return None;
+                    }
+                    self.instantiate(expr, &enum_.name, &[], expected)
+                }
+                None => {
+                    let name = ident.as_ref();
+                    if self.default_scope.is_some() && self.scope_of(name) == self.default_scope {
+                        self.report(
+                            Severity::Error,
+                            format!("a default value can't refer to argument `{name}`"),
+                        );
+                    }
+                    self.refer_top(name);
+                    self.read_local(name);
+                    self.lookup_local(name).cloned().flatten()
+                }
+            },
+            ast::SimpleExpr::Call(call) => self.check_call(call, expected),
+            ast::SimpleExpr::IfThenElse(if_then_else) => {
+                self.check_cond(&if_then_else.cond);
+                let then = self.check_simple_expr_as(&if_then_else.then, expected);
+                let else_ =
+                    self.check_simple_expr_as(&if_then_else.else_, expected.or(then.as_ref()));
+                match (then, else_) {
+                    (Some(then), Some(else_)) if same_type(&then, &else_) => Some(then),
+                    _ => None,
+                }
+            }
+            ast::SimpleExpr::Expr(inner) => match inner.as_ref() {
+                ast::Expr::Simple(inner) => self.check_simple_expr_as(inner, expected),
+                _ => self.check_simple_expr(expr),
+            },
+            _ => self.check_simple_expr(expr),
+        };
+        type_.map(|type_| self.resolve_alias(&type_))
+    }
+
+    // Returns the type of the expression, if known
+    fn check_simple_expr(&mut self, expr: &ast::SimpleExpr<'input>) -> Option<ast::Type<'input>> {
+        let type_ = match expr {
+            ast::SimpleExpr::Ident(_)
+            | ast::SimpleExpr::Call(_)
+            | ast::SimpleExpr::IfThenElse(_) => self.check_simple_expr_as(expr, None),
+            ast::SimpleExpr::IntLit(_) => Some(simple_type("int")),
+            ast::SimpleExpr::StringLit(_) => Some(simple_type("str")),
+            ast::SimpleExpr::InterpolatedString(string) => {
+                let types = string
+                    .exprs
+                    .iter()
+                    .map(|expr| self.check_simple_expr(expr).as_ref().map(owned_type))
+                    .collect();
+                self.annotations
+                    .interpolations
+                    .insert(node_key(string.as_ref()), types);
+                Some(simple_type("str"))
+            }
+            ast::SimpleExpr::CharLit(_) => Some(simple_type("char")),
+            ast::SimpleExpr::BoolLit(_) => Some(simple_type("bool")),
+            ast::SimpleExpr::Unit => Some(simple_type("()")),
+            ast::SimpleExpr::SelfRef => match self.self_type {
+                Some(name) => {
+                    let type_ = self.self_instance(name);
+                    self.capture(1, "self", Some(type_.clone()));
+                    Some(type_)
+                }
+                None => {
+                    self.report(
+                        Severity::Error,
+                        "`self` used outside of an impl".to_string(),
+                    );
+                    None
+                }
+            },
+            ast::SimpleExpr::Field(field) => match self.check_field(field).0 {
+                Member::Field(type_) => Some(type_),
+                Member::Method(_)
+                | Member::Builtin(_)
+                | Member::Spec(_)
+                | Member::Map(_)
+                | Member::Unknown => None,
+            },
+            ast::SimpleExpr::Index(index) => self.check_index(index),
+            ast::SimpleExpr::BinaryOp(binary_op) => self.check_binary_op(binary_op),
+            ast::SimpleExpr::Comprehension(comprehension) => {
+                self.check_comprehension(comprehension)
+            }
+            ast::SimpleExpr::Try(try_) => {
+                self.report(
+                    Severity::Error,
+                    "`try` can only be used as a statement or as the value of a `let`".to_string(),
+                );
+                self.check_try(try_)
+            }
+            ast::SimpleExpr::With(with) => {
+                self.report(
+                    Severity::Error,
+                    "`with` can only be used as a statement or as the value of a `let`".to_string(),
+                );
+                self.check_with(with)
+            }
+            ast::SimpleExpr::Yield(value) => self.check_yield(value.as_ref().as_ref()),
+            ast::SimpleExpr::Await(future) => self.check_await(future),
+            ast::SimpleExpr::BoolCond(bool_cond) => {
+                match bool_cond.as_ref() {
+                    ast::BoolCond::Not(expr) => self.check_cond(expr),
+                    ast::BoolCond::And(lhs, rhs) | ast::BoolCond::Or(lhs, rhs) => {
+                        self.check_cond(lhs);
+                        self.check_cond(rhs);
+                    }
+                }
+                Some(simple_type("bool"))
+            }
+            ast::SimpleExpr::Expr(expr) => {
+                // Go's switch is a statement, so it can only be lowered where its arms can assign
+                // or return their values
+                if let ast::Expr::Match(_) = expr.as_ref() {
+                    self.report(
+                        Severity::Error,
+                        "`match` can only be used as a statement, as the value of a `let` or as \
+                        the value of a match arm"
+                            .to_string(),
+                    );
+                }
+                self.check_expr(expr)
+            }
+        };
+        type_.map(|type_| self.resolve_alias(&type_))
+    }
+}
@@ -1,120 +1,1427 @@
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use match_tree::Decision;
+
+use crate::check::{Annotations, ArgValue, Iteration, Truthiness};
+
+mod match_tree;
 
 const BUFFER_SIZE: usize = 65536;
 
+// The Go package implementing the prelude, and the name it's imported as
+const RUNTIME_IMPORT: &str = "github.com/nu11ptr/interop/rt";
+const RUNTIME: &str = "rt";
+
+// Go escapes differ from Interop's, so literals are encoded again from their values
+fn go_escaped(s: &str, quote: char) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for char in s.chars() {
+        match char {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            _ if char == quote => {
+                escaped.push('\\');
+                escaped.push(char);
+            }
+            _ if char.is_ascii_control() => escaped.push_str(&format!("\\x{:02x}", char as u32)),
+            _ => escaped.push(char),
+        }
+    }
+
+    escaped
+}
+
+fn go_quoted(s: &str) -> String {
+    format!("\"{}\"", go_escaped(s, '"'))
+}
+
+fn go_char(char: char) -> String {
+    format!("'{}'", go_escaped(&char.to_string(), '\''))
+}
+
+// The 'fmt' verb for values of the type, which shows them like Interop would
+fn format_verb(type_: Option<&ast::Type<'_>>) -> &'static str {
+    match type_ {
+        Some(ast::Type::Simple(name)) => match name.as_ref() {
+            "str" | "String" => "%s",
+            "int" | "Int" => "%d",
+            "bool" | "Bool" => "%t",
+            "char" => "%c",
+            _ => "%v",
+        },
+        _ => "%v",
+    }
+}
+
+// What we need to know about a struct to construct it and match on it
+struct GoStruct {
+    fields: Vec<String>,
+    // Constructed by calling 'New<name>' instead of a composite literal
+    has_new: bool,
+    // Go type parameter list ('[T any]') and the type parameters as arguments ('[T]'), both empty
+    // unless the struct is generic
+    type_params: String,
+    type_args: String,
+}
+
+// Enums are sealed interfaces (with an unexported marker method) and one struct per variant,
+// unless no variant has a payload, in which case they are integers
+struct GoEnum {
+    c_like: bool,
+    variants: Vec<String>,
+    // Method specs of the interface, from the enum's impl blocks
+    methods: Vec<String>,
+    // As for structs - shared by the interface and the variant structs
+    type_params: String,
+    type_args: String,
+}
+
+// Where the value of a 'try', 'with' or 'match' goes
+#[derive(Clone, Copy, PartialEq)]
+enum BlockValue<'a> {
+    // The name a 'let' binds
+    Let(&'a str),
+    // Returned from the function
+    Return,
+    Discard,
+}
+
+// True if the type parameter is used as the key of a map (or the element of a set, which is one)
+// anywhere in the type
+fn is_map_key(type_: &ast::Type<'_>, param: &str) -> bool {
+    match type_ {
+        ast::Type::Simple(_) => false,
+        ast::Type::Generic(name, args) => {
+            let key = matches!(name.as_ref(), "map" | "set")
+                && matches!(args.first(), Some(ast::Type::Simple(key)) if key.as_ref() == param);
+            key || args.iter().any(|arg| is_map_key(arg, param))
+        }
+    }
+}
+
+// Go name of a method of the runtime package: 'unwrap_or' -> 'UnwrapOr'
+fn exported_name(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
 pub struct GoCodegen {
     type_map: HashMap<&'static str, &'static str>,
+    structs: HashMap<String, GoStruct>,
+    enums: HashMap<String, GoEnum>,
+    // Variant name -> # of fields (for variants that are Go structs)
+    variants: HashMap<String, usize>,
+    // Variants that are Go constants
+    const_variants: HashSet<String>,
+    // Structs, enums, variants and interfaces of the prelude, which are in the runtime package
+    runtime_types: HashSet<String>,
+    // Set when generated code refers to the runtime package, so it's only imported if used
+    uses_runtime: Cell<bool>,
+    // Set when generated code formats strings
+    uses_fmt: bool,
+    // Default values of func args -> their code, which is repeated in each call that leaves them
+    // out
+    default_vals: HashMap<usize, String>,
+    annotations: Annotations,
     indent: usize,
+    // Contexts of the 'with' blocks we are in, which are exited by explicit calls (innermost
+    // last)
+    contexts: Vec<String>,
+    // Whether we are in the body of a coroutine, which has no value to return
+    coroutine: bool,
+    // Used to generate unique temporary variable names
+    tmp_count: usize,
+    // Structs of the iterator comprehensions in the declaration being generated, which are
+    // emitted after it
+    iter_structs: String,
     code: String,
 }
 
+impl Default for GoCodegen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GoCodegen {
     pub fn new() -> Self {
         let mut type_map = HashMap::new();
         type_map.insert("Int", "int");
         type_map.insert("String", "string");
+        type_map.insert("str", "string");
+        type_map.insert("Bool", "bool");
+        type_map.insert("float", "float64");
+        type_map.insert("char", "rune");
+        type_map.insert("Any", "any");
+        type_map.insert("()", "struct{}");
 
         Self {
             type_map,
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            variants: HashMap::new(),
+            const_variants: HashSet::new(),
+            runtime_types: HashSet::new(),
+            uses_runtime: Cell::new(false),
+            uses_fmt: false,
+            default_vals: HashMap::new(),
+            annotations: Annotations::default(),
             indent: 0,
+            contexts: Vec::new(),
+            coroutine: false,
+            tmp_count: 0,
+            iter_structs: String::new(),
             code: String::with_capacity(BUFFER_SIZE),
         }
     }
 
-    fn map_type<'a>(&self, interop_type: &'a str) -> &'a str {
-        self.type_map.get(interop_type).unwrap_or(&interop_type)
+    // The annotations must be from checking the same declarations code is generated for
+    pub fn with_annotations(annotations: Annotations) -> Self {
+        Self {
+            annotations,
+            ..Self::new()
+        }
+    }
+
+    fn map_type(&self, interop_type: &str) -> String {
+        if self.runtime_types.contains(interop_type) {
+            self.uses_runtime.set(true);
+            return format!("{RUNTIME}.{}", exported_name(interop_type));
+        }
+
+        self.type_map
+            .get(interop_type)
+            .unwrap_or(&interop_type)
+            .to_string()
+    }
+
+    fn go_type(&self, type_: &ast::Type<'_>) -> String {
+        match type_ {
+            ast::Type::Simple(name) => self.map_type(name.as_ref()),
+            ast::Type::Generic(name, args) => {
+                let args: Vec<_> = args.iter().map(|arg| self.go_type(arg)).collect();
+
+                match (name.as_ref(), args.as_slice()) {
+                    ("list", [elem]) => format!("[]{elem}"),
+                    ("map", [key, value]) => format!("map[{key}]{value}"),
+                    // The elements are the keys
+                    ("set", [elem]) => format!("map[{elem}]struct{{}}"),
+                    ("iter", [elem]) => {
+                        self.uses_runtime.set(true);
+                        format!("{RUNTIME}.Iter[{elem}]")
+                    }
+                    ("coroutine", [out, in_]) => {
+                        self.uses_runtime.set(true);
+                        format!("*{RUNTIME}.Coroutine[{out}, {in_}]")
+                    }
+                    (name, args) => format!("{}[{}]", self.map_type(name), args.join(", ")),
+                }
+            }
+        }
+    }
+
+    // Go type parameter list of a generic func or type: '[T any, U Shape]'. Go map keys must be
+    // 'comparable', so 'types' are those the parameters are used in
+    fn go_type_params(
+        &self,
+        type_params: &[ast::TypeParam<'_>],
+        types: &[&ast::Type<'_>],
+    ) -> String {
+        if type_params.is_empty() {
+            return String::new();
+        }
+
+        let params: Vec<_> = type_params
+            .iter()
+            .map(|param| {
+                let name = param.name.as_ref();
+                match &param.bound {
+                    Some(bound) => format!("{name} {}", self.go_type(bound)),
+                    None if types.iter().any(|type_| is_map_key(type_, name)) => {
+                        format!("{name} comparable")
+                    }
+                    None => format!("{name} any"),
+                }
+            })
+            .collect();
+        format!("[{}]", params.join(", "))
+    }
+
+    // The type parameters of a generic type as the type arguments of its receivers: '[T, U]'
+    fn go_type_args(type_params: &[ast::TypeParam<'_>]) -> String {
+        if type_params.is_empty() {
+            return String::new();
+        }
+
+        let args: Vec<_> = type_params
+            .iter()
+            .map(|param| param.name.as_ref())
+            .collect();
+        format!("[{}]", args.join(", "))
+    }
+
+    // Name of a generic type's value (or pattern) with the type arguments the checks inferred for
+    // it, since Go can't infer them for composite literals: 'Some[int]'
+    fn instance_name<T>(&self, name: &str, node: &T) -> String {
+        let name = self.map_type(name);
+
+        match self.annotations.type_args(node) {
+            Some(args) => {
+                let args: Vec<_> = args.iter().map(|arg| self.go_type(arg)).collect();
+                format!("{name}[{}]", args.join(", "))
+            }
+            None => name,
+        }
+    }
+
+    fn push_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.code.push('\t');
+        }
+    }
+
+    fn new_tmp(&mut self, prefix: &str) -> String {
+        let tmp = format!("__{prefix}{}", self.tmp_count);
+        self.tmp_count += 1;
+        tmp
+    }
+
+    // Variant without a payload that is still a Go struct
+    fn is_unit_variant(&self, name: &str) -> bool {
+        self.variants.get(name) == Some(&0)
+    }
+
+    fn is_const_variant(&self, name: &str) -> bool {
+        self.const_variants.contains(name)
+    }
+
+    // Go expression for field 'idx' of a value narrowed to the constructor's type
+    fn ctor_field(&self, var: &str, ctor: &str, idx: usize) -> String {
+        match self
+            .structs
+            .get(ctor)
+            .and_then(|struct_| struct_.fields.get(idx))
+        {
+            Some(field) => format!("{var}.{field}"),
+            None => format!("{var}.F{idx}"),
+        }
+    }
+
+    fn collect_types(&mut self, decls: &[ast::Decl<'_>]) {
+        for decl in decls {
+            match decl {
+                ast::Decl::Struct(struct_) => {
+                    let fields = struct_
+                        .fields
+                        .iter()
+                        .map(|field| field.name.as_ref().to_string())
+                        .collect();
+                    let types: Vec<_> = struct_
+                        .fields
+                        .iter()
+                        .map(|field| &field.field_type)
+                        .collect();
+                    let type_params = self.go_type_params(&struct_.type_params, &types);
+                    self.structs.insert(
+                        struct_.name.as_ref().to_string(),
+                        GoStruct {
+                            fields,
+                            has_new: false,
+                            type_params,
+                            type_args: Self::go_type_args(&struct_.type_params),
+                        },
+                    );
+                }
+                ast::Decl::Enum(enum_) => {
+                    let c_like = enum_.is_c_like();
+                    let types: Vec<_> = enum_
+                        .variants
+                        .iter()
+                        .flat_map(|variant| &variant.fields)
+                        .collect();
+                    let type_params = self.go_type_params(&enum_.type_params, &types);
+                    for variant in &enum_.variants {
+                        let name = variant.name.as_ref().to_string();
+                        if c_like {
+                            self.const_variants.insert(name);
+                        } else {
+                            self.variants.insert(name, variant.fields.len());
+                        }
+                    }
+                    self.enums.insert(
+                        enum_.name.as_ref().to_string(),
+                        GoEnum {
+                            c_like,
+                            variants: enum_
+                                .variants
+                                .iter()
+                                .map(|variant| variant.name.as_ref().to_string())
+                                .collect(),
+                            methods: Vec::new(),
+                            type_params,
+                            type_args: Self::go_type_args(&enum_.type_params),
+                        },
+                    );
+                }
+                ast::Decl::Func(_)
+                | ast::Decl::Interface(_)
+                | ast::Decl::Impl(_)
+                | ast::Decl::Type(_)
+                | ast::Decl::Let(_)
+                | ast::Decl::Const(_) => {}
+            }
+        }
+
+        for decl in decls {
+            if let ast::Decl::Impl(impl_) = decl {
+                if let Some(struct_) = self.structs.get_mut(impl_.name.as_ref()) {
+                    struct_.has_new |= impl_.funcs.iter().any(|func| func.name.as_ref() == "new");
+                } else if self.enums.contains_key(impl_.name.as_ref()) {
+                    let methods: Vec<_> = impl_
+                        .funcs
+                        .iter()
+                        .map(|func| self.method_spec(func))
+                        .collect();
+                    if let Some(enum_) = self.enums.get_mut(impl_.name.as_ref()) {
+                        enum_.methods.extend(methods);
+                    }
+                }
+            }
+        }
+
+        // Funcs can be called before they are declared
+        for decl in decls {
+            match decl {
+                ast::Decl::Func(func) => self.collect_default_vals(func),
+                ast::Decl::Impl(impl_) => {
+                    for func in &impl_.funcs {
+                        self.collect_default_vals(func);
+                    }
+                }
+                ast::Decl::Struct(_)
+                | ast::Decl::Enum(_)
+                | ast::Decl::Interface(_)
+                | ast::Decl::Type(_)
+                | ast::Decl::Let(_)
+                | ast::Decl::Const(_) => {}
+            }
+        }
+    }
+
+    fn collect_default_vals(&mut self, func: &ast::Func<'_>) {
+        for default_val in func.args.iter().filter_map(|arg| arg.default_val.as_ref()) {
+            let code = mem::take(&mut self.code);
+            self.gen_simple_expr(default_val);
+            let default_code = mem::replace(&mut self.code, code);
+            self.default_vals
+                .insert(Annotations::default_val_key(default_val), default_code);
+        }
+    }
+
+    // Method as written in a Go interface
+    fn method_spec(&self, func: &ast::Func<'_>) -> String {
+        let args = func
+            .args
+            .iter()
+            .map(|arg| format!("{} {}", arg.name.as_ref(), self.go_type(&arg.arg_type)))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match &func.body {
+            ast::FuncBody::Block(Some(type_), _) => {
+                format!("{}({args}) {}", func.name.as_ref(), self.go_type(type_))
+            }
+            _ => format!("{}({args})", func.name.as_ref()),
+        }
+    }
+
+    // The prelude is only declared, since the runtime package implements it
+    pub fn gen_code(&mut self, prelude: &[ast::Decl<'_>], decls: &[ast::Decl<'_>]) -> &str {
+        self.collect_types(prelude);
+        for decl in prelude {
+            match decl {
+                ast::Decl::Struct(struct_) => {
+                    self.runtime_types.insert(struct_.name.as_ref().to_string());
+                }
+                ast::Decl::Enum(enum_) => {
+                    self.runtime_types.insert(enum_.name.as_ref().to_string());
+                    self.runtime_types.extend(
+                        enum_
+                            .variants
+                            .iter()
+                            .map(|variant| variant.name.as_ref().to_string()),
+                    );
+                }
+                // Go has its own 'error'
+                ast::Decl::Interface(interface) if interface.name.as_ref() != "error" => {
+                    self.runtime_types
+                        .insert(interface.name.as_ref().to_string());
+                }
+                ast::Decl::Func(_)
+                | ast::Decl::Interface(_)
+                | ast::Decl::Impl(_)
+                | ast::Decl::Type(_)
+                | ast::Decl::Let(_)
+                | ast::Decl::Const(_) => {}
+            }
+        }
+        self.collect_types(decls);
+
+        for (idx, decl) in decls.iter().enumerate() {
+            if idx > 0 {
+                self.code.push_str("\n\n");
+            }
+
+            match decl {
+                ast::Decl::Func(func) => {
+                    self.gen_func(func);
+                }
+                ast::Decl::Struct(struct_) => {
+                    self.gen_struct(struct_);
+                }
+                ast::Decl::Enum(enum_) => {
+                    self.gen_enum(enum_);
+                }
+                ast::Decl::Interface(interface) => {
+                    self.gen_interface(interface);
+                }
+                ast::Decl::Impl(impl_) => {
+                    self.gen_impl(impl_);
+                }
+                ast::Decl::Type(type_decl) => {
+                    self.gen_type_decl(type_decl);
+                }
+                ast::Decl::Let(let_) => {
+                    self.gen_top_let(let_);
+                }
+                ast::Decl::Const(const_) => {
+                    self.gen_const(const_);
+                }
+            }
+            let iter_structs = mem::take(&mut self.iter_structs);
+            self.code.push_str(&iter_structs);
+        }
+
+        let mut imports = String::new();
+        if self.uses_fmt {
+            imports.push_str("import \"fmt\"\n");
+        }
+        if self.uses_runtime.get() {
+            imports.push_str(&format!("import {RUNTIME} \"{RUNTIME_IMPORT}\"\n"));
+        }
+        if !imports.is_empty() {
+            imports.push('\n');
+            self.code.insert_str(0, &imports);
+        }
+        &self.code
+    }
+
+    fn gen_struct(&mut self, struct_: &ast::Struct<'_>) {
+        let name = struct_.name.as_ref();
+        self.code.push_str("type ");
+        self.code.push_str(name);
+        self.code.push_str(&self.structs[name].type_params);
+        self.code.push_str(" struct {\n");
+
+        for field in &struct_.fields {
+            self.code.push('\t');
+            self.code.push_str(field.name.as_ref());
+            self.code.push(' ');
+            self.code.push_str(&self.go_type(&field.field_type));
+            self.code.push('\n');
+        }
+
+        self.code.push('}');
+    }
+
+    fn gen_enum(&mut self, enum_: &ast::Enum<'_>) {
+        if enum_.is_c_like() {
+            self.gen_const_enum(enum_);
+            return;
+        }
+
+        let name = enum_.name.as_ref();
+        let marker = format!("is{name}");
+        let type_params = self.enums[name].type_params.clone();
+        let type_args = self.enums[name].type_args.clone();
+        // The marker takes the type parameters, so the variants of `Tree[int]` don't also
+        // satisfy `Tree[string]`
+        let marker_params = type_args.trim_start_matches('[').trim_end_matches(']');
+
+        self.code.push_str(&format!(
+            "type {name}{type_params} interface {{\n\t{marker}({marker_params})\n"
+        ));
+        for method in &self.enums[name].methods {
+            self.code.push('\t');
+            self.code.push_str(method);
+            self.code.push('\n');
+        }
+        self.code.push('}');
+
+        for variant in &enum_.variants {
+            self.code.push_str("\n\n");
+            self.code.push_str("type ");
+            self.code.push_str(variant.name.as_ref());
+            self.code.push_str(&type_params);
+
+            if variant.fields.is_empty() {
+                self.code.push_str(" struct{}");
+            } else {
+                self.code.push_str(" struct {\n");
+                for (idx, field) in variant.fields.iter().enumerate() {
+                    self.code
+                        .push_str(&format!("\tF{idx} {}\n", self.go_type(field)));
+                }
+                self.code.push('}');
+            }
+
+            self.code.push_str(&format!(
+                "\n\nfunc ({}{type_args}) {marker}({marker_params}) {{}}",
+                variant.name.as_ref()
+            ));
+        }
+    }
+
+    // Enum where no variant has a payload: a Go integer type with a constant per variant, plus
+    // conversions to and from the variant names
+    fn gen_const_enum(&mut self, enum_: &ast::Enum<'_>) {
+        let name = enum_.name.as_ref();
+        let explicit = enum_.variants.iter().any(|variant| variant.value.is_some());
+
+        self.code.push_str(&format!("type {name} int\n\nconst (\n"));
+        for (idx, (variant, value)) in enum_.variants.iter().zip(enum_.values()).enumerate() {
+            let variant = variant.name.as_ref();
+            if explicit {
+                self.code
+                    .push_str(&format!("\t{variant} {name} = {value}\n"));
+            } else if idx == 0 {
+                self.code.push_str(&format!("\t{variant} {name} = iota\n"));
+            } else {
+                self.code.push_str(&format!("\t{variant}\n"));
+            }
+        }
+        self.code.push_str(")\n\n");
+
+        self.code.push_str(&format!(
+            "func (self {name}) String() string {{\n\tswitch self {{\n"
+        ));
+        for variant in &enum_.variants {
+            let variant = variant.name.as_ref();
+            self.code
+                .push_str(&format!("\tcase {variant}:\n\t\treturn \"{variant}\"\n"));
+        }
+        self.code.push_str(&format!(
+            "\tdefault:\n\t\tpanic(\"invalid {name} value\")\n\t}}\n}}\n\n"
+        ));
+
+        // So 'str()' can be called from Interop
+        self.code.push_str(&format!(
+            "func (self {name}) str() string {{\n\treturn self.String()\n}}\n\n"
+        ));
+
+        // Called as '<enum>.from_str(s)' from Interop
+        let option = self.map_type("Option");
+        let (some, none) = (self.map_type("Some"), self.map_type("None"));
+        self.code.push_str(&format!(
+            "func {name}FromStr(s string) {option}[{name}] {{\n\tswitch s {{\n"
+        ));
+        for variant in &enum_.variants {
+            let variant = variant.name.as_ref();
+            self.code.push_str(&format!(
+                "\tcase \"{variant}\":\n\t\treturn {some}[{name}]{{F0: {variant}}}\n"
+            ));
+        }
+        self.code.push_str(&format!(
+            "\tdefault:\n\t\treturn {none}[{name}]{{}}\n\t}}\n}}"
+        ));
+    }
+
+    // Implementations are found by Go, so 'impl <interface> for <type>' only emits the methods
+    fn gen_interface(&mut self, interface: &ast::Interface<'_>) {
+        self.code.push_str("type ");
+        self.code.push_str(interface.name.as_ref());
+
+        if interface.methods.is_empty() {
+            self.code.push_str(" interface{}");
+            return;
+        }
+
+        self.code.push_str(" interface {\n");
+        for method in &interface.methods {
+            let args = method
+                .arg_types
+                .iter()
+                .map(|type_| self.go_type(type_))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            self.code
+                .push_str(&format!("\t{}({args})", method.name.as_ref()));
+            if let Some(ret_type) = &method.ret_type {
+                self.code.push(' ');
+                self.code.push_str(&self.go_type(ret_type));
+            }
+            self.code.push('\n');
+        }
+        self.code.push('}');
+    }
+
+    fn gen_impl(&mut self, impl_: &ast::Impl<'_>) {
+        // Go interfaces can't have methods, so each variant of an enum gets its own copy
+        let receivers = match (
+            self.structs.get(impl_.name.as_ref()),
+            self.enums.get(impl_.name.as_ref()),
+        ) {
+            (Some(struct_), _) => vec![format!("{}{}", impl_.name.as_ref(), struct_.type_args)],
+            (None, Some(enum_)) if !enum_.c_like => enum_
+                .variants
+                .iter()
+                .map(|variant| format!("{variant}{}", enum_.type_args))
+                .collect(),
+            _ => vec![impl_.name.as_ref().to_string()],
+        };
+
+        let mut first = true;
+        for func in &impl_.funcs {
+            if func.name.as_ref() == "new" {
+                if !first {
+                    self.code.push_str("\n\n");
+                }
+                first = false;
+
+                self.gen_constructor(impl_.name.as_ref(), func);
+                continue;
+            }
+
+            for receiver in &receivers {
+                if !first {
+                    self.code.push_str("\n\n");
+                }
+                first = false;
+
+                // Value receiver, since nothing can be assigned to after initialization
+                self.code.push_str("func (self ");
+                self.code.push_str(receiver);
+                self.code.push_str(") ");
+                self.code.push_str(func.name.as_ref());
+                self.gen_func_args(func);
+                self.gen_func_body(func);
+            }
+        }
+    }
+
+    // Fields are initialized on a zero value 'self', which is returned at the end
+    fn gen_constructor(&mut self, name: &str, func: &ast::Func<'_>) {
+        let (type_params, type_args) = self
+            .structs
+            .get(name)
+            .map(|struct_| (struct_.type_params.clone(), struct_.type_args.clone()))
+            .unwrap_or_default();
+        self.code.push_str(&format!("func New{name}{type_params}"));
+        let name = format!("{name}{type_args}");
+
+        self.gen_func_args(func);
+        self.code.push_str(&name);
+        self.code.push(' ');
+
+        match &func.body {
+            ast::FuncBody::Block(_, block) => {
+                self.code.push_str("{\n");
+                self.indent += 1;
+
+                self.push_indent();
+                self.code.push_str(&format!("var self {name}\n"));
+                self.gen_stmts(block, false);
+                self.push_indent();
+                self.code.push_str("return self\n");
+
+                self.indent -= 1;
+                self.push_indent();
+                self.code.push('}');
+            }
+            ast::FuncBody::Expr(expr) => {
+                self.gen_single_stmt_block(expr, true);
+            }
+        }
+    }
+
+    fn gen_type_decl(&mut self, type_decl: &ast::TypeDecl<'_>) {
+        let sep = if type_decl.alias { " = " } else { " " };
+        self.code.push_str(&format!(
+            "type {}{sep}{}",
+            type_decl.name.as_ref(),
+            self.go_type(&type_decl.target)
+        ));
+    }
+
+    // A 'let' in a func declares a new name rather than assigning, so 'let' and 'let var' are
+    // both a Go 'var' (which Go initializes in the order their dependencies need)
+    fn gen_top_let(&mut self, let_: &ast::Let<'_>) {
+        if let ast::LetTarget::Ident(name) = &let_.target {
+            self.gen_top_binding("var", name, let_.let_type.as_ref(), &let_.value);
+        }
+    }
+
+    fn gen_const(&mut self, const_: &ast::Const<'_>) {
+        self.gen_top_binding(
+            "const",
+            &const_.name,
+            const_.const_type.as_ref(),
+            &const_.value,
+        );
+    }
+
+    fn gen_top_binding(
+        &mut self,
+        keyword: &str,
+        name: &ast::Ident<'_>,
+        type_: Option<&ast::Type<'_>>,
+        value: &ast::SimpleExpr<'_>,
+    ) {
+        self.code.push_str(keyword);
+        self.code.push(' ');
+        self.code.push_str(name.as_ref());
+        if let Some(type_) = type_ {
+            self.code.push(' ');
+            self.code.push_str(&self.go_type(type_));
+        }
+        self.code.push_str(" = ");
+        self.gen_simple_expr(value);
+    }
+
+    fn gen_func(&mut self, func: &ast::Func) {
+        // Write function signature
+        self.code.push_str("func ");
+        self.code.push_str(func.name.as_ref());
+
+        let mut types: Vec<_> = func.args.iter().map(|arg| &arg.arg_type).collect();
+        if let ast::FuncBody::Block(Some(ret_type), _) = &func.body {
+            types.push(ret_type);
+        }
+        self.code
+            .push_str(&self.go_type_params(&func.type_params, &types));
+
+        self.gen_func_args(func);
+        self.gen_func_body(func);
+    }
+
+    fn gen_func_args(&mut self, func: &ast::Func) {
+        self.code.push('(');
+        for (idx, arg) in func.args.iter().enumerate() {
+            self.code.push_str(arg.name.as_ref());
+            self.code.push(' ');
+            self.code.push_str(&self.go_type(&arg.arg_type));
+
+            if idx < func.args.len() - 1 {
+                self.code.push_str(", ");
+            }
+        }
+        self.code.push_str(") ");
+    }
+
+    fn gen_func_body(&mut self, func: &ast::Func) {
+        // Returning from a nested function doesn't leave the 'with' blocks it is in
+        let contexts = mem::take(&mut self.contexts);
+        let coroutine = mem::take(&mut self.coroutine);
+
+        match &func.body {
+            _ if func.is_async => {
+                self.gen_async_body(func);
+            }
+            ast::FuncBody::Block(Some(type_ @ ast::Type::Generic(name, args)), block)
+                if name.as_ref() == "coroutine" && args.len() == 2 =>
+            {
+                self.code.push_str(&self.go_type(type_));
+                self.code.push_str(" {\n");
+                self.indent += 1;
+                self.gen_coroutine(&args[0], &args[1], block);
+                self.indent -= 1;
+                self.code.push('}');
+            }
+            ast::FuncBody::Block(Some(type_), block) => {
+                self.code.push_str(&self.go_type(type_));
+                self.code.push(' ');
+                self.gen_block(block, true);
+            }
+            ast::FuncBody::Block(None, block) => {
+                self.gen_block(block, true);
+            }
+            ast::FuncBody::Expr(expr) => {
+                self.gen_single_stmt_block(expr, true);
+            }
+        }
+
+        self.contexts = contexts;
+        self.coroutine = coroutine;
+    }
+
+    // Formatted by 'fmt.Sprintf', with the verb for each expression chosen by its type
+    fn gen_interpolated_string(&mut self, string: &ast::InterpolatedString<'_>) {
+        self.uses_fmt = true;
+        let types = self.annotations.interpolation_types(string);
+
+        let mut format = String::from("\"");
+        for (idx, piece) in string.strings.iter().enumerate() {
+            format.push_str(&go_escaped(piece.value(), '"').replace('%', "%%"));
+
+            if idx < string.exprs.len() {
+                format.push_str(format_verb(types.get(idx).and_then(Option::as_ref)));
+            }
+        }
+        format.push('"');
+
+        self.code.push_str(&format!("fmt.Sprintf({format}"));
+        for expr in &string.exprs {
+            self.code.push_str(", ");
+            self.gen_simple_expr(expr);
+        }
+        self.code.push(')');
+    }
+
+    // The body runs on its own goroutine, and the 'Future' returned right away gets its value
+    fn gen_async_body(&mut self, func: &ast::Func) {
+        let value_type = match &func.body {
+            ast::FuncBody::Block(Some(type_), _) => type_.clone(),
+            _ => ast::Type::Simple(ast::Ident::from_str("()")),
+        };
+        let future_type =
+            ast::Type::Generic(ast::Ident::from_str("Future"), vec![value_type.clone()]);
+        self.code.push_str(&self.go_type(&future_type));
+        self.code.push_str(" {\n");
+        let value_type = self.go_type(&value_type);
+        self.indent += 1;
+        self.push_indent();
+        self.code
+            .push_str(&format!("return {RUNTIME}.Async(func() {value_type} {{\n"));
+        self.indent += 1;
+
+        match &func.body {
+            ast::FuncBody::Block(Some(_), block) => self.gen_stmts(block, true),
+            ast::FuncBody::Block(None, block) => {
+                self.gen_stmts(block, false);
+                self.push_indent();
+                self.code.push_str("return struct{}{}\n");
+            }
+            ast::FuncBody::Expr(expr) => {
+                self.push_indent();
+                self.code.push_str("return ");
+                self.gen_simple_expr(expr);
+                self.code.push('\n');
+            }
+        }
+
+        self.indent -= 1;
+        self.push_indent();
+        self.code.push_str("})\n");
+        self.indent -= 1;
+        self.code.push('}');
+    }
+
+    // For now the body runs on its own goroutine, which the runtime switches to and from over
+    // channels. Its 'yield' hands a value over and waits to be resumed
+    // TODO: transform the body into a state machine, so no goroutine is needed
+    fn gen_coroutine(&mut self, out: &ast::Type<'_>, in_: &ast::Type<'_>, block: &ast::Block<'_>) {
+        self.push_indent();
+        self.code.push_str(&format!(
+            "return {RUNTIME}.NewCoroutine(func(yield func({}) {}) {{\n",
+            self.go_type(out),
+            self.go_type(in_)
+        ));
+
+        self.indent += 1;
+        self.coroutine = true;
+        self.gen_stmts(block, false);
+        self.indent -= 1;
+
+        self.push_indent();
+        self.code.push_str("})\n");
+    }
+
+    fn gen_field(&mut self, field: &ast::Field<'_>) {
+        if self.annotations.is_static_member(field) {
+            self.gen_simple_expr(&field.target);
+            self.code.push_str(&exported_name(field.field.as_ref()));
+            return;
+        }
+
+        self.gen_simple_expr(&field.target);
+        self.code.push('.');
+
+        if self.annotations.is_runtime_method(field) {
+            self.code.push_str(&exported_name(field.field.as_ref()));
+        } else {
+            self.code.push_str(field.field.as_ref());
+        }
+    }
+
+    fn gen_let(&mut self, let_: &ast::Let<'_>) {
+        self.gen_let_target(let_);
+        self.gen_simple_expr(&let_.value);
+    }
+
+    fn gen_let_target(&mut self, let_: &ast::Let<'_>) {
+        match (&let_.target, &let_.let_type) {
+            (ast::LetTarget::Ident(name), Some(type_)) => {
+                self.code
+                    .push_str(&format!("var {} {} = ", name.as_ref(), self.go_type(type_)));
+            }
+            (ast::LetTarget::Ident(name), None) => {
+                self.code.push_str(name.as_ref());
+                self.code.push_str(" := ");
+            }
+            (ast::LetTarget::SelfField(field), _) => {
+                self.code.push_str("self.");
+                self.code.push_str(field.as_ref());
+                self.code.push_str(" = ");
+            }
+        }
+    }
+
+    // Struct without a 'new' function or a variant - fields are matched to named args, then
+    // positional ones
+    fn gen_composite_lit(&mut self, name: &str, fields: &[String], call: &ast::Call<'_>) {
+        let mut positional = fields.iter();
+        let args: Vec<_> = call
+            .args
+            .iter()
+            .map(|arg| match &arg.name {
+                Some(arg_name) => (arg_name.as_ref().to_string(), &arg.expr),
+                None => (positional.next().cloned().unwrap_or_default(), &arg.expr),
+            })
+            .collect();
+
+        self.code.push_str(name);
+        self.code.push('{');
+        for (idx, (field, expr)) in args.iter().enumerate() {
+            self.code.push_str(field);
+            self.code.push_str(": ");
+            self.gen_simple_expr(expr);
+
+            if idx < args.len() - 1 {
+                self.code.push_str(", ");
+            }
+        }
+        self.code.push('}');
+    }
+
+    fn gen_call(&mut self, call: &ast::Call<'_>) {
+        if let (Some(map_func), ast::SimpleExpr::Field(field)) =
+            (self.annotations.map_func(call), &call.target)
+        {
+            self.uses_runtime.set(true);
+            self.code.push_str(&format!("{RUNTIME}.{map_func}("));
+            self.gen_simple_expr(&field.target);
+            self.code.push_str(", ");
+            self.gen_simple_expr(&call.args[0].expr);
+            self.code.push(')');
+            return;
+        }
+
+        match &call.target {
+            ast::SimpleExpr::Ident(ident) => match self.structs.get(ident.as_ref()) {
+                Some(struct_) if struct_.has_new => {
+                    self.code.push_str("New");
+                    self.code
+                        .push_str(&self.instance_name(ident.as_ref(), call));
+                }
+                Some(struct_) => {
+                    let fields = struct_.fields.clone();
+                    let name = self.instance_name(ident.as_ref(), call);
+                    self.gen_composite_lit(&name, &fields, call);
+                    return;
+                }
+                None => match self.variants.get(ident.as_ref()) {
+                    Some(&arity) => {
+                        let fields: Vec<_> = (0..arity).map(|idx| format!("F{idx}")).collect();
+                        let name = self.instance_name(ident.as_ref(), call);
+                        self.gen_composite_lit(&name, &fields, call);
+                        return;
+                    }
+                    None => self.code.push_str(ident.as_ref()),
+                },
+            },
+            target => self.gen_simple_expr(target),
+        }
+
+        self.code.push('(');
+
+        match self.annotations.call_args(call).map(<[_]>::to_vec) {
+            // Go has neither named nor default args, so they are all given in order
+            Some(values) => {
+                for (idx, value) in values.into_iter().enumerate() {
+                    if idx > 0 {
+                        self.code.push_str(", ");
+                    }
+
+                    match value {
+                        ArgValue::Given(arg_idx) => self.gen_simple_expr(&call.args[arg_idx].expr),
+                        ArgValue::Default(key) => self.code.push_str(&self.default_vals[&key]),
+                    }
+                }
+            }
+            None => {
+                for (idx, arg) in call.args.iter().enumerate() {
+                    self.gen_simple_expr(&arg.expr);
+
+                    if idx < call.args.len() - 1 {
+                        self.code.push_str(", ");
+                    }
+                }
+            }
+        }
+
+        self.code.push(')');
+    }
+
+    // Wraps expressions in parens when something binds tighter than they do (method calls, '!')
+    fn gen_operand(&mut self, expr: &ast::SimpleExpr<'_>) {
+        match expr {
+            ast::SimpleExpr::BinaryOp(_)
+            | ast::SimpleExpr::BoolCond(_)
+            | ast::SimpleExpr::IfThenElse(_) => {
+                self.code.push('(');
+                self.gen_simple_expr(expr);
+                self.code.push(')');
+            }
+            _ => self.gen_simple_expr(expr),
+        }
+    }
+
+    // Go only accepts bool, so the checks record how other conditions are converted
+    fn gen_cond(&mut self, cond: &ast::SimpleExpr<'_>) {
+        match self.annotations.truthiness(cond) {
+            Some(Truthiness::NonZero) => {
+                self.gen_operand(cond);
+                self.code.push_str(" != 0");
+            }
+            Some(Truthiness::NonEmpty) => {
+                self.code.push_str("len(");
+                self.gen_simple_expr(cond);
+                self.code.push_str(") != 0");
+            }
+            Some(Truthiness::NotNone) => {
+                self.gen_operand(cond);
+                // Composite literals must be wrapped to be used in an 'if' condition
+                if self.is_const_variant("None") {
+                    self.code.push_str(" != None");
+                } else {
+                    let none = self.instance_name("None", cond);
+                    self.code.push_str(&format!(" != ({none}{{}})"));
+                }
+            }
+            Some(Truthiness::Method) => {
+                self.gen_operand(cond);
+                self.code.push_str(".__bool__()");
+            }
+            None => self.gen_simple_expr(cond),
+        }
     }
 
-    fn push_indent(&mut self) {
-        for _ in 0..self.indent {
-            self.code.push('\t');
+    fn gen_binary_op(&mut self, binary_op: &ast::BinaryOp<'_>) {
+        match self.annotations.magic_method(binary_op) {
+            Some(method) => {
+                if binary_op.op == ast::BinaryOperator::NotEqual {
+                    self.code.push('!');
+                }
+                self.gen_operand(&binary_op.lhs);
+                self.code.push('.');
+                self.code.push_str(method);
+                self.code.push('(');
+                self.gen_simple_expr(&binary_op.rhs);
+                self.code.push(')');
+            }
+            None => {
+                self.gen_simple_expr(&binary_op.lhs);
+                self.code.push(' ');
+                self.code.push_str(binary_op.op.symbol());
+                self.code.push(' ');
+                self.gen_simple_expr(&binary_op.rhs);
+            }
         }
     }
 
-    pub fn gen_code(&mut self, decls: &[ast::Decl<'_>]) -> &str {
-        for decl in decls {
-            match decl {
-                ast::Decl::Func(func) => {
-                    self.gen_func(func);
-                }
+    // Lists, maps and sets are built by a function literal that is called right away, so it can be
+    // used wherever an expression can. Without annotations, the source is taken to be a list and
+    // the elements to be 'Any'
+    fn gen_comprehension(&mut self, comprehension: &ast::Comprehension<'_>) {
+        let (iteration, type_) = match self.annotations.comprehension(comprehension) {
+            Some((iteration, type_)) => (iteration, type_.clone()),
+            None => {
+                let any = || ast::Type::Simple(ast::Ident::from_str("Any"));
+                let (name, args) = match &comprehension.kind {
+                    ast::ComprehensionKind::List(_) => ("list", vec![any()]),
+                    ast::ComprehensionKind::Map(_, _) => ("map", vec![any(), any()]),
+                    ast::ComprehensionKind::Set(_) => ("set", vec![any()]),
+                    ast::ComprehensionKind::Iter(_) => ("iter", vec![any()]),
+                };
+                let type_ = ast::Type::Generic(ast::Ident::from_str(name), args);
+                (Iteration::Elements, type_)
             }
+        };
+
+        if let ast::ComprehensionKind::Iter(elem) = &comprehension.kind {
+            self.gen_iter_comprehension(comprehension, elem, iteration, &type_);
+            return;
         }
 
-        &self.code
+        self.code
+            .push_str(&format!("func() {} {{\n", self.go_type(&type_)));
+        self.indent += 1;
+
+        let kind = &comprehension.kind;
+        let result = self.new_tmp("result");
+        self.push_indent();
+        self.code
+            .push_str(&format!("{result} := {}{{}}\n", self.go_type(&type_)));
+
+        self.gen_comprehension_loop(comprehension, iteration, None, |codegen| match kind {
+            ast::ComprehensionKind::List(elem) => {
+                codegen
+                    .code
+                    .push_str(&format!("{result} = append({result}, "));
+                codegen.gen_simple_expr(elem);
+                codegen.code.push(')');
+            }
+            ast::ComprehensionKind::Map(key, value) => {
+                codegen.code.push_str(&format!("{result}["));
+                codegen.gen_simple_expr(key);
+                codegen.code.push_str("] = ");
+                codegen.gen_simple_expr(value);
+            }
+            ast::ComprehensionKind::Set(elem) => {
+                codegen.code.push_str(&format!("{result}["));
+                codegen.gen_simple_expr(elem);
+                codegen.code.push_str("] = struct{}{}");
+            }
+            ast::ComprehensionKind::Iter(_) => {
+                unreachable!("use gen_iter_comprehension")
+            }
+        });
+
+        self.push_indent();
+        self.code.push_str(&format!("return {result}\n"));
+
+        self.indent -= 1;
+        self.push_indent();
+        self.code.push_str("}()");
     }
 
-    fn gen_func(&mut self, func: &ast::Func) {
-        // Write function signature
-        self.code.push_str("func ");
-        self.code.push_str(func.name.as_ref());
+    // A value of a struct generated for the comprehension, whose 'Next' method runs the loop until
+    // the next element. The struct is emitted after the declaration we are in
+    fn gen_iter_comprehension(
+        &mut self,
+        comprehension: &ast::Comprehension<'_>,
+        elem: &ast::SimpleExpr<'_>,
+        iteration: Iteration,
+        type_: &ast::Type<'_>,
+    ) {
+        let iter_struct = self
+            .annotations
+            .iter_struct(comprehension)
+            .cloned()
+            .unwrap_or_default();
+        let elem_type = match type_ {
+            ast::Type::Generic(_, args) => self.go_type(&args[0]),
+            ast::Type::Simple(_) => unreachable!("iterators have a type argument"),
+        };
+        let source_type = format!("{RUNTIME}.Iter[{elem_type}]");
+        self.uses_runtime.set(true);
 
-        // Write function args
-        self.code.push('(');
-        for (idx, arg) in func.args.iter().enumerate() {
-            self.code.push_str(arg.name.as_ref());
-            self.code.push(' ');
-            self.code.push_str(self.map_type(arg.arg_type.as_ref()));
+        let name = self.new_tmp("iter");
+        let types: Vec<_> = iter_struct.locals.iter().map(|(_, type_)| type_).collect();
+        let type_params = self.go_type_params(&iter_struct.type_params, &types);
+        let type_args = Self::go_type_args(&iter_struct.type_params);
 
-            if idx < func.args.len() - 1 {
-                self.code.push_str(", ");
-            }
+        // The source is always an iterator, so the values are only computed when needed
+        self.code
+            .push_str(&format!("{source_type}(&{name}{type_args}{{__source: "));
+        let (open, close) = match iteration {
+            Iteration::Elements => (format!("{RUNTIME}.Elements("), ")"),
+            Iteration::Keys => (format!("{RUNTIME}.Keys("), ")"),
+            Iteration::Next => (String::new(), ""),
+        };
+        self.code.push_str(&open);
+        self.gen_simple_expr(&comprehension.source);
+        self.code.push_str(close);
+        for (local, _) in &iter_struct.locals {
+            self.code.push_str(&format!(", {local}: {local}"));
         }
-        self.code.push_str(") ");
+        self.code.push_str("})");
 
-        // Write function body
-        match &func.body {
-            ast::FuncBody::Block(Some(type_), block) => {
-                self.code.push_str(self.map_type(type_.as_ref()));
-                self.code.push(' ');
-                self.gen_block(block, true);
-            }
-            ast::FuncBody::Block(None, block) => {
-                self.gen_block(block, true);
+        let code = mem::take(&mut self.code);
+        let indent = mem::replace(&mut self.indent, 0);
+
+        self.code.push_str(&format!(
+            "type {name}{type_params} struct {{\n\t__source {source_type}\n"
+        ));
+        for (local, type_) in &iter_struct.locals {
+            self.code
+                .push_str(&format!("\t{local} {}\n", self.go_type(type_)));
+        }
+        self.code.push_str(&format!(
+            "}}\n\nfunc (__self *{name}{type_args}) Next() ({elem_type}, bool) {{\n"
+        ));
+        self.indent += 1;
+        for (local, _) in &iter_struct.locals {
+            self.push_indent();
+            self.code.push_str(&format!("{local} := __self.{local}\n"));
+        }
+        self.gen_comprehension_loop(
+            comprehension,
+            Iteration::Next,
+            Some("__self.__source"),
+            |codegen| {
+                codegen.code.push_str("return ");
+                codegen.gen_simple_expr(elem);
+                codegen.code.push_str(", true");
+            },
+        );
+        self.push_indent();
+        self.code
+            .push_str(&format!("return {RUNTIME}.Done[{elem_type}]()\n}}"));
+
+        // Whoever stops early closes the source through it, which may be a coroutine
+        self.code.push_str(&format!(
+            "\n\nfunc (__self *{name}{type_args}) Close() {{\n\t{RUNTIME}.Close(__self.__source)\n}}"
+        ));
+
+        let iter_code = mem::replace(&mut self.code, code);
+        self.indent = indent;
+        self.iter_structs.push_str("\n\n");
+        self.iter_structs.push_str(&iter_code);
+    }
+
+    // The loop over the source (or the iterator named 'source'), with 'gen_body' generating the
+    // single statement run for each element that passes the condition. Each line is indented and
+    // ends with a newline
+    fn gen_comprehension_loop(
+        &mut self,
+        comprehension: &ast::Comprehension<'_>,
+        iteration: Iteration,
+        source: Option<&str>,
+        gen_body: impl FnOnce(&mut Self),
+    ) {
+        let name = comprehension.name.as_ref();
+
+        self.push_indent();
+        match iteration {
+            // Go won't declare '_' with ':='
+            Iteration::Elements | Iteration::Keys if name == "_" => {
+                self.code.push_str("for range ");
             }
-            ast::FuncBody::Expr(expr) => {
-                self.gen_single_stmt_block(expr, true);
+            Iteration::Elements => self.code.push_str(&format!("for _, {name} := range ")),
+            Iteration::Keys => self.code.push_str(&format!("for {name} := range ")),
+            Iteration::Next => {
+                let source = match source {
+                    Some(source) => source.to_string(),
+                    None => {
+                        let source = self.new_tmp("source");
+                        self.code.push_str(&format!("{source} := "));
+                        self.gen_simple_expr(&comprehension.source);
+                        self.code.push('\n');
+                        // A coroutine left unfinished (by a panic) would keep its goroutine
+                        self.push_indent();
+                        self.code
+                            .push_str(&format!("defer {RUNTIME}.Close({source})\n"));
+                        self.uses_runtime.set(true);
+                        self.push_indent();
+                        source
+                    }
+                };
+                let ok = self.new_tmp("ok");
+                self.code.push_str(&format!(
+                    "for {name}, {ok} := {source}.Next(); {ok}; {name}, {ok} = {source}.Next() {{\n"
+                ));
             }
         }
-    }
+        if iteration != Iteration::Next {
+            self.gen_simple_expr(&comprehension.source);
+            self.code.push_str(" {\n");
+        }
+        self.indent += 1;
 
-    fn gen_field(&mut self, field: &ast::Field<'_>) {
-        self.gen_simple_expr(&field.target);
-        self.code.push('.');
-        self.code.push_str(field.field.as_ref());
+        if let Some(cond) = &comprehension.cond {
+            self.push_indent();
+            self.code.push_str("if ");
+            self.gen_cond(cond);
+            self.code.push_str(" {\n");
+            self.indent += 1;
+        }
+
+        self.push_indent();
+        gen_body(self);
+        self.code.push('\n');
+
+        if comprehension.cond.is_some() {
+            self.indent -= 1;
+            self.push_indent();
+            self.code.push_str("}\n");
+        }
+
+        self.indent -= 1;
+        self.push_indent();
+        self.code.push_str("}\n");
     }
 
-    fn gen_call(&mut self, call: &ast::Call<'_>) {
-        self.gen_simple_expr(&call.target);
+    fn gen_index(&mut self, index: &ast::Index<'_>) {
+        self.gen_operand(&index.target);
 
-        self.code.push('(');
+        let (open, close) = match self.annotations.magic_method(index) {
+            Some(method) => {
+                self.code.push('.');
+                self.code.push_str(method);
+                ('(', ')')
+            }
+            None => ('[', ']'),
+        };
 
-        // TODO: Named args may be out of order - we ignore this for now
-        for (idx, arg) in call.args.iter().enumerate() {
-            self.gen_simple_expr(&arg.expr);
+        self.code.push(open);
+        for (idx, expr) in index.indexes.iter().enumerate() {
+            self.gen_simple_expr(expr);
 
-            if idx < call.args.len() - 1 {
+            if idx < index.indexes.len() - 1 {
                 self.code.push_str(", ");
             }
         }
-
-        self.code.push(')');
+        self.code.push(close);
     }
 
     fn gen_bool_cond(&mut self, bool_cond: &ast::BoolCond<'_>) {
         match bool_cond {
             ast::BoolCond::Not(expr) => {
-                self.code.push_str("!");
-                self.gen_simple_expr(expr);
+                self.code.push('!');
+                match self.annotations.truthiness(expr) {
+                    Some(Truthiness::Method) => self.gen_cond(expr),
+                    // The conversion is a comparison, so it needs wrapping
+                    Some(_) => {
+                        self.code.push('(');
+                        self.gen_cond(expr);
+                        self.code.push(')');
+                    }
+                    None => self.gen_operand(expr),
+                }
             }
             ast::BoolCond::And(lhs, rhs) => {
-                self.gen_simple_expr(lhs);
+                self.gen_cond(lhs);
                 self.code.push_str(" && ");
-                self.gen_simple_expr(rhs);
+                self.gen_cond(rhs);
             }
             ast::BoolCond::Or(lhs, rhs) => {
-                self.gen_simple_expr(lhs);
+                self.gen_cond(lhs);
                 self.code.push_str(" || ");
-                self.gen_simple_expr(rhs);
+                self.gen_cond(rhs);
             }
         }
     }
@@ -122,7 +1429,7 @@ impl GoCodegen {
     // In Go, if is always a statement, so more handling is needed here
     fn gen_if_then_else(&mut self, if_then_else: &ast::IfThenElse<'_>) {
         self.code.push_str("if ");
-        self.gen_simple_expr(&if_then_else.cond);
+        self.gen_cond(&if_then_else.cond);
         self.code.push(' ');
         self.gen_single_stmt_block(&if_then_else.then, false);
         self.code.push_str(" else ");
@@ -133,7 +1440,7 @@ impl GoCodegen {
     // In Go, if is always a statement, so more handling is needed here
     fn gen_if(&mut self, if_: &ast::If<'_>) {
         self.code.push_str("if ");
-        self.gen_simple_expr(&if_.cond);
+        self.gen_cond(&if_.cond);
         self.code.push(' ');
         self.gen_block(&if_.then_body, false);
 
@@ -151,38 +1458,262 @@ impl GoCodegen {
         }
     }
 
+    fn gen_match(&mut self, match_: &ast::Match<'_>, value: BlockValue<'_>) {
+        // The value may be tested more than once, so make sure it is only evaluated once
+        let (target, tmp) = match &match_.expr {
+            ast::SimpleExpr::Ident(ident) if !self.variants.contains_key(ident.as_ref()) => {
+                (ident.as_ref().to_string(), false)
+            }
+            ast::SimpleExpr::SelfRef => ("self".to_string(), false),
+            _ => (self.new_tmp("match"), true),
+        };
+
+        let decision = match_tree::build(self, &match_.arms, &target);
+
+        if tmp {
+            if decision.mentions(&target) {
+                self.code.push_str(&format!("{target} := "));
+            } else {
+                self.code.push_str("_ = ");
+            }
+            self.gen_simple_expr(&match_.expr);
+            self.code.push('\n');
+            self.push_indent();
+        }
+
+        match &decision {
+            // No tests needed, but we still need a new scope for the bindings
+            Decision::Leaf { .. } | Decision::Fail => {
+                self.code.push_str("{\n");
+                self.indent += 1;
+                self.gen_decision_body(&decision, &match_.arms, value);
+                self.indent -= 1;
+                self.push_indent();
+                self.code.push('}');
+            }
+            _ => self.gen_decision(&decision, &match_.arms, value),
+        }
+    }
+
+    // Go's switch is a statement, so each arm returns, assigns or discards its value itself
+    fn gen_arm_body(&mut self, body: &ast::SimpleExpr<'_>, value: BlockValue<'_>) {
+        self.push_indent();
+
+        if let Some(match_) = body.as_match() {
+            self.gen_match(match_, value);
+            self.code.push('\n');
+            return;
+        }
+
+        match value {
+            BlockValue::Return => self.code.push_str("return "),
+            BlockValue::Let(name) => self.code.push_str(&format!("{name} = ")),
+            // Only some expressions are allowed as statements in Go
+            BlockValue::Discard => {
+                if !matches!(
+                    body,
+                    ast::SimpleExpr::Call(_)
+                        | ast::SimpleExpr::Await(_)
+                        | ast::SimpleExpr::Yield(_)
+                        | ast::SimpleExpr::IfThenElse(_)
+                ) {
+                    self.code.push_str("_ = ");
+                }
+            }
+        }
+        self.gen_simple_expr(body);
+        self.code.push('\n');
+    }
+
+    // A 'let' whose value is a 'match' is declared before the switch, whose arms assign it
+    fn gen_let_match(&mut self, let_: &ast::Let<'_>, name: &str, match_: &ast::Match<'_>) {
+        let let_type = let_
+            .let_type
+            .as_ref()
+            .or_else(|| self.annotations.match_type(match_))
+            .map(|type_| self.go_type(type_))
+            .unwrap_or_default();
+        self.push_indent();
+        self.code.push_str(&format!("var {name} {let_type}\n"));
+        self.push_indent();
+        self.gen_match(match_, BlockValue::Let(name));
+        self.code.push('\n');
+    }
+
+    // Each line is indented and ends with a newline
+    fn gen_decision_body(
+        &mut self,
+        decision: &Decision,
+        arms: &[ast::MatchArm<'_>],
+        value: BlockValue<'_>,
+    ) {
+        match decision {
+            Decision::Leaf { arm, bindings } => {
+                for (name, value) in bindings {
+                    self.push_indent();
+                    self.code.push_str(&format!("{name} := {value}\n"));
+                    // Go doesn't allow unused variables
+                    self.push_indent();
+                    self.code.push_str(&format!("_ = {name}\n"));
+                }
+                self.gen_arm_body(&arms[*arm].body, value);
+            }
+            // This also keeps Go happy when the match is the last statement of a function
+            // (all paths must return)
+            Decision::Fail => {
+                self.push_indent();
+                self.code.push_str("panic(\"no match arm matched\")\n");
+            }
+            _ => {
+                self.push_indent();
+                self.gen_decision(decision, arms, value);
+                self.code.push('\n');
+            }
+        }
+    }
+
+    // Emits a (possibly nested) switch. Since nothing follows a switch in its case, we never need
+    // to break out of the outer switches
+    fn gen_decision(
+        &mut self,
+        decision: &Decision,
+        arms: &[ast::MatchArm<'_>],
+        value: BlockValue<'_>,
+    ) {
+        let (cases, default) = match decision {
+            Decision::Switch {
+                target,
+                cases,
+                default,
+            } => {
+                self.code.push_str(&format!("switch {target} {{\n"));
+                (cases, default)
+            }
+            Decision::TypeSwitch {
+                target,
+                var,
+                cases,
+                default,
+            } => {
+                match var {
+                    // Allows type switches on values that aren't statically an interface
+                    Some(var) => self
+                        .code
+                        .push_str(&format!("switch {var} := any({target}).(type) {{\n")),
+                    None => self
+                        .code
+                        .push_str(&format!("switch any({target}).(type) {{\n")),
+                }
+                (cases, default)
+            }
+            Decision::Leaf { .. } | Decision::Fail => {
+                unreachable!("not a switch - use gen_decision_body")
+            }
+        };
+
+        for (values, case) in cases {
+            self.push_indent();
+            self.code
+                .push_str(&format!("case {}:\n", values.join(", ")));
+            self.indent += 1;
+            self.gen_decision_body(case, arms, value);
+            self.indent -= 1;
+        }
+
+        self.push_indent();
+        self.code.push_str("default:\n");
+        self.indent += 1;
+        self.gen_decision_body(default, arms, value);
+        self.indent -= 1;
+
+        self.push_indent();
+        self.code.push('}');
+    }
+
     fn gen_simple_expr(&mut self, expr: &ast::SimpleExpr<'_>) {
         match expr {
             ast::SimpleExpr::Ident(ident) => {
-                self.code.push_str(ident.as_ref());
+                // Variants without a payload are still Go structs
+                if self.is_unit_variant(ident.as_ref()) {
+                    self.code
+                        .push_str(&self.instance_name(ident.as_ref(), expr));
+                    self.code.push_str("{}");
+                } else {
+                    self.code.push_str(ident.as_ref());
+                }
             }
             ast::SimpleExpr::IntLit(int_lit) => {
                 self.code.push_str(&format!("{}", int_lit.value));
             }
-            ast::SimpleExpr::StringLit(string_lit) => {
-                self.code.push_str(&string_lit.unparsed);
+            ast::SimpleExpr::StringLit(string_lit) => match string_lit.raw() {
+                // Go raw strings can't contain backquotes (and drop carriage returns)
+                Some(raw) if raw.contains(['`', '\r']) => self.code.push_str(&go_quoted(raw)),
+                Some(raw) => self.code.push_str(&format!("`{raw}`")),
+                None => self.code.push_str(&go_quoted(string_lit.value())),
+            },
+            ast::SimpleExpr::InterpolatedString(string) => {
+                self.gen_interpolated_string(string);
             }
             ast::SimpleExpr::CharLit(char_lit) => {
-                self.code.push_str(&char_lit.unparsed);
+                self.code.push_str(&go_char(char_lit.value()));
             }
             ast::SimpleExpr::BoolLit(bool_lit) => {
                 self.code.push_str(&format!("{}", bool_lit.0));
             }
+            ast::SimpleExpr::SelfRef => {
+                self.code.push_str("self");
+            }
+            ast::SimpleExpr::Unit => {
+                self.code.push_str("struct{}{}");
+            }
             ast::SimpleExpr::Field(field) => {
                 self.gen_field(field);
             }
             ast::SimpleExpr::Call(call) => {
                 self.gen_call(call);
             }
+            ast::SimpleExpr::Await(future) => {
+                self.gen_simple_expr(future);
+                self.code.push_str(".Await()");
+            }
+            ast::SimpleExpr::Yield(value) => {
+                self.code.push_str("yield(");
+                match value.as_ref() {
+                    Some(value) => self.gen_simple_expr(value),
+                    None => self.code.push_str("struct{}{}"),
+                }
+                self.code.push(')');
+            }
+            ast::SimpleExpr::Index(index) => {
+                self.gen_index(index);
+            }
             ast::SimpleExpr::IfThenElse(if_then_else) => {
                 self.gen_if_then_else(if_then_else);
             }
             ast::SimpleExpr::BoolCond(bool_cond) => {
                 self.gen_bool_cond(bool_cond);
             }
-            ast::SimpleExpr::Expr(expr) => {
-                self.gen_expr(expr);
+            ast::SimpleExpr::BinaryOp(binary_op) => {
+                self.gen_binary_op(binary_op);
             }
+            ast::SimpleExpr::Comprehension(comprehension) => {
+                self.gen_comprehension(comprehension);
+            }
+            ast::SimpleExpr::Try(_) => {
+                unreachable!("`try` is a statement or the value of a `let` - use gen_try")
+            }
+            ast::SimpleExpr::With(_) => {
+                unreachable!("`with` is a statement or the value of a `let` - use gen_with")
+            }
+            ast::SimpleExpr::Expr(expr) => match &**expr {
+                // Keep the source's parens, they may be overriding precedence
+                ast::Expr::Simple(simple_expr) => {
+                    self.code.push('(');
+                    self.gen_simple_expr(simple_expr);
+                    self.code.push(')');
+                }
+                _ => self.gen_expr(expr),
+            },
         }
     }
 
@@ -191,6 +1722,9 @@ impl GoCodegen {
             ast::Expr::If(if_) => {
                 self.gen_if(if_);
             }
+            ast::Expr::Match(match_) => {
+                self.gen_match(match_, BlockValue::Discard);
+            }
             ast::Expr::Simple(simple_expr) => {
                 self.gen_simple_expr(simple_expr);
             }
@@ -219,28 +1753,343 @@ impl GoCodegen {
         self.code.push_str("{\n");
         self.indent += 1;
 
+        self.gen_stmts(block, func_block);
+
+        self.indent -= 1;
+        self.push_indent();
+        self.code.push('}');
+    }
+
+    // Each statement is indented and ends with a newline
+    fn gen_stmts(&mut self, block: &ast::Block<'_>, func_block: bool) {
         for (idx, stmt_or_expr) in block.stmt_or_exprs.iter().enumerate() {
-            self.push_indent();
-            // This is a hack to handle the last statement in a block
-            // and it won't work for more complex cases
-            if func_block && idx == block.stmt_or_exprs.len() - 1 {
-                self.code.push_str("return ");
+            let last = func_block && idx == block.stmt_or_exprs.len() - 1;
+            self.gen_stmt(stmt_or_expr, last);
+        }
+    }
+
+    // 'last' is set for the last statement of a function, whose value is returned
+    fn gen_stmt(&mut self, stmt_or_expr: &ast::StmtOrExpr<'_>, last: bool) {
+        self.gen_stmt_code(stmt_or_expr, last);
+
+        // Go doesn't allow unused variables
+        if let ast::StmtOrExpr::Let(
+            let_ @ ast::Let {
+                target: ast::LetTarget::Ident(name),
+                ..
+            },
+        ) = stmt_or_expr
+        {
+            if self.annotations.is_unused(let_) {
+                self.push_indent();
+                self.code.push_str(&format!("_ = {}\n", name.as_ref()));
             }
+        }
+    }
 
-            match stmt_or_expr {
-                ast::StmtOrExpr::Func(func) => {
-                    self.gen_func(func);
+    fn gen_stmt_code(&mut self, stmt_or_expr: &ast::StmtOrExpr<'_>, last: bool) {
+        // 'try' and 'with' are lowered to several statements
+        let value = if last {
+            BlockValue::Return
+        } else {
+            BlockValue::Discard
+        };
+        match stmt_or_expr {
+            ast::StmtOrExpr::Let(ast::Let {
+                target: ast::LetTarget::Ident(name),
+                value: ast::SimpleExpr::Try(try_),
+                ..
+            }) => {
+                self.gen_try(try_, BlockValue::Let(name.as_ref()));
+                return;
+            }
+            ast::StmtOrExpr::Let(ast::Let {
+                target: ast::LetTarget::Ident(name),
+                value: ast::SimpleExpr::With(with),
+                ..
+            }) => {
+                self.gen_with(with, BlockValue::Let(name.as_ref()));
+                return;
+            }
+            ast::StmtOrExpr::Let(
+                let_ @ ast::Let {
+                    target: ast::LetTarget::Ident(name),
+                    value,
+                    ..
+                },
+            ) if value.as_match().is_some() => {
+                if let Some(match_) = value.as_match() {
+                    self.gen_let_match(let_, name.as_ref(), match_);
                 }
-                ast::StmtOrExpr::Expr(expr) => {
-                    self.gen_expr(expr);
+                return;
+            }
+            ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::Try(try_))) => {
+                self.gen_try(try_, value);
+                return;
+            }
+            ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::With(with))) => {
+                self.gen_with(with, value);
+                return;
+            }
+            _ => {}
+        }
+
+        self.push_indent();
+
+        // This is a hack to handle the last statement in a block
+        // and it won't work for more complex cases
+        if last
+            && !matches!(
+                stmt_or_expr,
+                ast::StmtOrExpr::Let(_) | ast::StmtOrExpr::Expr(ast::Expr::Match(_))
+            )
+        {
+            self.code.push_str("return ");
+        }
+
+        match stmt_or_expr {
+            ast::StmtOrExpr::Func(func) => {
+                self.collect_default_vals(func);
+                self.gen_func(func);
+            }
+            ast::StmtOrExpr::Let(let_) => {
+                self.gen_let(let_);
+            }
+            ast::StmtOrExpr::Unwrap(_) => {
+                unreachable!("`<-` is only in `try` blocks - use gen_try")
+            }
+            // Match is a statement in Go, so each arm returns instead
+            ast::StmtOrExpr::Expr(ast::Expr::Match(match_)) => {
+                self.gen_match(match_, value);
+            }
+            ast::StmtOrExpr::Expr(expr) => {
+                self.gen_expr(expr);
+            }
+        }
+
+        self.code.push('\n');
+    }
+
+    // Each value is unwrapped into itself and an error, and if there is an error, the value of
+    // the catch block is returned. Each line is indented and ends with a newline
+    fn gen_try(&mut self, try_: &ast::Try<'_>, value: BlockValue<'_>) {
+        match &try_.body {
+            ast::TryBody::Expr(expr) => {
+                let name = match value {
+                    BlockValue::Let(name) => name.to_string(),
+                    BlockValue::Return => self.new_tmp("try"),
+                    BlockValue::Discard => "_".to_string(),
+                };
+                self.gen_unwrap(&name, expr, try_, try_);
+                self.gen_try_value(&name, value);
+            }
+            ast::TryBody::Block(block) => {
+                let stmts = &block.stmt_or_exprs;
+                for (idx, stmt_or_expr) in stmts.iter().enumerate() {
+                    let last = idx == stmts.len() - 1;
+
+                    match (stmt_or_expr, value) {
+                        (ast::StmtOrExpr::Unwrap(unwrap), _) => {
+                            let name = unwrap.name.as_ref();
+                            self.gen_unwrap(name, &unwrap.value, unwrap, try_);
+                            if last {
+                                self.gen_try_value(name, value);
+                            }
+                        }
+                        (ast::StmtOrExpr::Expr(ast::Expr::Simple(expr)), BlockValue::Let(name))
+                            if last =>
+                        {
+                            self.push_indent();
+                            self.code.push_str(&format!("{name} := "));
+                            self.gen_simple_expr(expr);
+                            self.code.push('\n');
+                        }
+                        _ => self.gen_stmt(stmt_or_expr, last && value == BlockValue::Return),
+                    }
                 }
             }
+        }
+    }
+
+    // The value of a 'try' is already in 'name'
+    fn gen_try_value(&mut self, name: &str, value: BlockValue<'_>) {
+        match value {
+            BlockValue::Let(let_name) if let_name != name => {
+                self.push_indent();
+                self.code.push_str(&format!("{let_name} := {name}\n"));
+            }
+            BlockValue::Return => {
+                self.push_indent();
+                self.code.push_str(&format!("return {name}\n"));
+            }
+            BlockValue::Let(_) | BlockValue::Discard => {}
+        }
+    }
+
+    // Values are unwrapped by the 'Get' method of 'Result' - types with a '__try__' method are
+    // converted to one first
+    fn gen_unwrap<T>(
+        &mut self,
+        name: &str,
+        value: &ast::SimpleExpr<'_>,
+        node: &T,
+        try_: &ast::Try<'_>,
+    ) {
+        let err_name = try_.err_name.as_ref().map_or("err", |name| name.as_ref());
+
+        self.push_indent();
+        // Go won't redeclare 'err' with ':=' unless something else is new
+        if name == "_" {
+            self.code.push_str(&format!("if _, {err_name} := "));
+        } else {
+            self.code.push_str(&format!("{name}, {err_name} := "));
+        }
+
+        self.gen_operand(value);
+        if let Some(method) = self.annotations.magic_method(node) {
+            self.code.push_str(&format!(".{method}()"));
+        }
+        self.code.push_str(".Get()");
 
+        if name == "_" {
+            self.code.push_str(&format!("; {err_name} != nil {{\n"));
+        } else {
             self.code.push('\n');
+            self.push_indent();
+            self.code.push_str(&format!("if {err_name} != nil {{\n"));
         }
 
+        self.indent += 1;
+        self.gen_catch(try_);
         self.indent -= 1;
+
         self.push_indent();
-        self.code.push('}');
+        self.code.push_str("}\n");
+    }
+
+    // The value of the catch block is returned, after exiting the contexts of the 'with' blocks
+    // it is in. A coroutine has no value to return, so it just finishes
+    fn gen_catch(&mut self, try_: &ast::Try<'_>) {
+        let stmts = &try_.catch_body.stmt_or_exprs;
+
+        if self.coroutine {
+            self.gen_stmts(&try_.catch_body, false);
+            self.gen_exits();
+            self.push_indent();
+            self.code.push_str("return\n");
+            return;
+        }
+
+        for (idx, stmt_or_expr) in stmts.iter().enumerate() {
+            let last = idx == stmts.len() - 1;
+
+            match stmt_or_expr {
+                ast::StmtOrExpr::Expr(ast::Expr::Simple(expr)) if last => {
+                    // The value is evaluated before the contexts are exited
+                    let ret = (!self.contexts.is_empty()).then(|| self.new_tmp("ret"));
+
+                    self.push_indent();
+                    match &ret {
+                        Some(ret) => self.code.push_str(&format!("{ret} := ")),
+                        None => self.code.push_str("return "),
+                    }
+
+                    match self.annotations.wrapped_error(expr) {
+                        // The error is returned from a function returning a 'Result'
+                        Some(value_type) => {
+                            self.code.push_str(&format!(
+                                "{}[{}]{{F0: ",
+                                self.map_type("Err"),
+                                self.go_type(value_type)
+                            ));
+                            self.gen_simple_expr(expr);
+                            self.code.push('}');
+                        }
+                        None => self.gen_simple_expr(expr),
+                    }
+                    self.code.push('\n');
+
+                    if let Some(ret) = ret {
+                        self.gen_exits();
+                        self.push_indent();
+                        self.code.push_str(&format!("return {ret}\n"));
+                    }
+                }
+                _ if last => {
+                    self.gen_exits();
+                    self.gen_stmt(stmt_or_expr, true);
+                }
+                _ => self.gen_stmt(stmt_or_expr, false),
+            }
+        }
+    }
+
+    // A Go block, so the names it declares stay in it. Each line is indented and ends with a
+    // newline
+    fn gen_with(&mut self, with: &ast::With<'_>, value: BlockValue<'_>) {
+        if let BlockValue::Let(let_name) = value {
+            let let_type = self
+                .annotations
+                .with_type(with)
+                .map(|type_| self.go_type(type_))
+                .unwrap_or_default();
+            self.push_indent();
+            self.code.push_str(&format!("var {let_name} {let_type}\n"));
+        }
+
+        self.push_indent();
+        self.code.push_str("{\n");
+        self.indent += 1;
+
+        let name = match &with.name {
+            Some(name) => name.as_ref().to_string(),
+            None => self.new_tmp("with"),
+        };
+        self.push_indent();
+        self.code.push_str(&format!("{name} := "));
+        self.gen_simple_expr(&with.context);
+        self.code.push('\n');
+        self.push_indent();
+        self.code.push_str(&format!("{name}.Enter()\n"));
+
+        // Nothing follows it in the function, so it can be exited by 'defer', which also covers
+        // early returns and panics
+        if value == BlockValue::Return {
+            self.push_indent();
+            self.code.push_str(&format!("defer {name}.Exit()\n"));
+            self.gen_stmts(&with.body, true);
+        } else {
+            self.contexts.push(name.clone());
+            let stmts = &with.body.stmt_or_exprs;
+            for (idx, stmt_or_expr) in stmts.iter().enumerate() {
+                match (stmt_or_expr, value) {
+                    (ast::StmtOrExpr::Expr(ast::Expr::Simple(expr)), BlockValue::Let(let_name))
+                        if idx == stmts.len() - 1 =>
+                    {
+                        self.push_indent();
+                        self.code.push_str(&format!("{let_name} = "));
+                        self.gen_simple_expr(expr);
+                        self.code.push('\n');
+                    }
+                    _ => self.gen_stmt(stmt_or_expr, false),
+                }
+            }
+            self.contexts.pop();
+
+            self.push_indent();
+            self.code.push_str(&format!("{name}.Exit()\n"));
+        }
+
+        self.indent -= 1;
+        self.push_indent();
+        self.code.push_str("}\n");
+    }
+
+    // Exits the contexts of the 'with' blocks we are in, innermost first
+    fn gen_exits(&mut self) {
+        for name in self.contexts.clone().iter().rev() {
+            self.push_indent();
+            self.code.push_str(&format!("{name}.Exit()\n"));
+        }
     }
 }
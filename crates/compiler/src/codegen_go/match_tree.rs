@@ -0,0 +1,354 @@
+// Compiles the arms of a match into a decision tree so that no part of the value is tested more
+// than once along any path, and each test is shared by every arm that needs it. Based on
+// "Compiling Pattern Matching to Good Decision Trees" (Maranget, 2008), always testing what the
+// first remaining arm needs next (the "first row" heuristic).
+//
+// Targets and bindings are Go expressions. Fields of constructors are read from the variable a
+// type switch narrows the value into (by name for structs, otherwise by position: 'F0', 'F1', etc.)
+
+use super::{go_char, go_quoted, GoCodegen};
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Decision {
+    // The arm matched. Bindings are (name, Go expression)
+    Leaf {
+        arm: usize,
+        bindings: Vec<(String, String)>,
+    },
+    // No arm matched
+    Fail,
+    // Go switch on constant values. A case can have several values
+    Switch {
+        target: String,
+        cases: Vec<(Vec<String>, Decision)>,
+        default: Box<Decision>,
+    },
+    // Go type switch. 'var' holds the narrowed value in each case, if it is used by any case
+    TypeSwitch {
+        target: String,
+        var: Option<String>,
+        cases: Vec<(Vec<String>, Decision)>,
+        default: Box<Decision>,
+    },
+}
+
+impl Decision {
+    // True if the Go variable is used anywhere in this decision
+    pub(super) fn mentions(&self, var: &str) -> bool {
+        let is_var = |expr: &str| {
+            expr == var
+                || expr
+                    .strip_prefix(var)
+                    .is_some_and(|field| field.starts_with('.'))
+        };
+
+        match self {
+            Decision::Leaf { bindings, .. } => bindings.iter().any(|(_, value)| is_var(value)),
+            Decision::Fail => false,
+            Decision::Switch {
+                target,
+                cases,
+                default,
+            } => {
+                is_var(target)
+                    || cases.iter().any(|(_, decision)| decision.mentions(var))
+                    || default.mentions(var)
+            }
+            Decision::TypeSwitch {
+                target,
+                cases,
+                default,
+                ..
+            } => {
+                is_var(target)
+                    || cases.iter().any(|(_, decision)| decision.mentions(var))
+                    || default.mentions(var)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Row<'a, 'input> {
+    // Patterns still to be tested, each against a Go expression (left to right)
+    tests: Vec<(String, &'a ast::Pattern<'input>)>,
+    bindings: Vec<(String, String)>,
+    arm: usize,
+}
+
+// What a pattern tests for
+#[derive(PartialEq)]
+enum Test {
+    // Go constant
+    Value(String),
+    // Go type
+    Type(String),
+}
+
+pub(super) fn build(codegen: &mut GoCodegen, arms: &[ast::MatchArm<'_>], target: &str) -> Decision {
+    let mut rows = Vec::with_capacity(arms.len());
+    for (arm, match_arm) in arms.iter().enumerate() {
+        let row = Row {
+            tests: vec![(target.to_string(), &match_arm.pattern)],
+            bindings: Vec::new(),
+            arm,
+        };
+        push_row(codegen, row, &mut rows);
+    }
+
+    compile(codegen, rows)
+}
+
+// Moves anything that isn't a test into the bindings and expands alternatives into one row each
+fn push_row<'a, 'input>(
+    codegen: &GoCodegen,
+    mut row: Row<'a, 'input>,
+    rows: &mut Vec<Row<'a, 'input>>,
+) {
+    let mut idx = 0;
+
+    while idx < row.tests.len() {
+        let (target, pattern) = &row.tests[idx];
+
+        match pattern {
+            ast::Pattern::Wildcard => {
+                row.tests.remove(idx);
+            }
+            // A bare variant name is a test
+            ast::Pattern::Bind(name)
+                if codegen.is_unit_variant(name.as_ref())
+                    || codegen.is_const_variant(name.as_ref()) =>
+            {
+                idx += 1
+            }
+            ast::Pattern::Bind(name) => {
+                row.bindings
+                    .push((name.as_ref().to_string(), target.clone()));
+                row.tests.remove(idx);
+            }
+            ast::Pattern::As(pattern, name) => {
+                row.bindings
+                    .push((name.as_ref().to_string(), target.clone()));
+                row.tests[idx].1 = pattern;
+            }
+            ast::Pattern::Alt(patterns) => {
+                for pattern in patterns {
+                    let mut alt_row = row.clone();
+                    alt_row.tests[idx].1 = pattern;
+                    push_row(codegen, alt_row, rows);
+                }
+                return;
+            }
+            _ => idx += 1,
+        }
+    }
+
+    rows.push(row);
+}
+
+fn test_for(codegen: &GoCodegen, pattern: &ast::Pattern<'_>) -> Test {
+    match pattern {
+        ast::Pattern::IntLit(int_lit) => Test::Value(format!("{}", int_lit.value)),
+        ast::Pattern::StringLit(string_lit) => Test::Value(go_quoted(string_lit.value())),
+        ast::Pattern::CharLit(char_lit) => Test::Value(go_char(char_lit.value())),
+        ast::Pattern::BoolLit(bool_lit) => Test::Value(format!("{}", bool_lit.0)),
+        ast::Pattern::Type(type_pattern) => Test::Type(codegen.go_type(&type_pattern.pattern_type)),
+        // Constructors are always Go types of the same name
+        ast::Pattern::Ctor(ctor_pattern) => {
+            Test::Type(codegen.instance_name(ctor_pattern.name.as_ref(), pattern))
+        }
+        ast::Pattern::Bind(name) if codegen.is_unit_variant(name.as_ref()) => {
+            Test::Type(codegen.instance_name(name.as_ref(), pattern))
+        }
+        ast::Pattern::Bind(name) if codegen.is_const_variant(name.as_ref()) => {
+            Test::Value(name.as_ref().to_string())
+        }
+        ast::Pattern::Wildcard
+        | ast::Pattern::Bind(_)
+        | ast::Pattern::As(_, _)
+        | ast::Pattern::Alt(_) => unreachable!("not a test - removed by push_row"),
+    }
+}
+
+// Index and test of the row's pattern for the target, if it has one
+fn row_test(codegen: &GoCodegen, row: &Row<'_, '_>, target: &str) -> Option<(usize, Test)> {
+    row.tests
+        .iter()
+        .position(|(test_target, _)| test_target == target)
+        .map(|idx| (idx, test_for(codegen, row.tests[idx].1)))
+}
+
+fn compile(codegen: &mut GoCodegen, rows: Vec<Row<'_, '_>>) -> Decision {
+    let Some(first) = rows.first() else {
+        return Decision::Fail;
+    };
+
+    // Nothing left to test, so the first row always matches
+    let Some((target, pattern)) = first.tests.first() else {
+        return Decision::Leaf {
+            arm: first.arm,
+            bindings: first.bindings.clone(),
+        };
+    };
+
+    let target = target.clone();
+    match test_for(codegen, pattern) {
+        Test::Value(_) => compile_switch(codegen, rows, target),
+        Test::Type(_) => compile_type_switch(codegen, rows, target),
+    }
+}
+
+// Drops cases that do the same thing as the default and merges cases that do the same thing
+// (if allowed by 'can_merge')
+fn share_cases(
+    cases: Vec<(String, Decision)>,
+    default: &Decision,
+    can_merge: impl Fn(&Decision) -> bool,
+) -> Vec<(Vec<String>, Decision)> {
+    let mut shared: Vec<(Vec<String>, Decision)> = Vec::with_capacity(cases.len());
+
+    for (case, decision) in cases {
+        if decision == *default {
+            continue;
+        }
+
+        let existing = shared
+            .iter_mut()
+            .find(|(_, existing)| *existing == decision && can_merge(existing));
+        match existing {
+            Some((existing_cases, _)) => existing_cases.push(case),
+            None => shared.push((vec![case], decision)),
+        }
+    }
+
+    shared
+}
+
+fn compile_switch(codegen: &mut GoCodegen, rows: Vec<Row<'_, '_>>, target: String) -> Decision {
+    let mut values = Vec::new();
+    for row in &rows {
+        if let Some((_, Test::Value(value))) = row_test(codegen, row, &target) {
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+    }
+
+    let mut cases = Vec::with_capacity(values.len());
+    for value in values {
+        let mut case_rows = Vec::with_capacity(rows.len());
+
+        for row in &rows {
+            match row_test(codegen, row, &target) {
+                Some((idx, Test::Value(row_value))) => {
+                    if row_value == value {
+                        let mut row = row.clone();
+                        row.tests.remove(idx);
+                        case_rows.push(row);
+                    }
+                }
+                // Type tests on the same target are left for later
+                Some((_, Test::Type(_))) | None => case_rows.push(row.clone()),
+            }
+        }
+
+        cases.push((value, compile(codegen, case_rows)));
+    }
+
+    let default_rows = rows
+        .iter()
+        .filter(|row| !matches!(row_test(codegen, row, &target), Some((_, Test::Value(_)))))
+        .cloned()
+        .collect();
+    let default = compile(codegen, default_rows);
+
+    Decision::Switch {
+        target,
+        cases: share_cases(cases, &default, |_| true),
+        default: Box::new(default),
+    }
+}
+
+fn compile_type_switch(
+    codegen: &mut GoCodegen,
+    rows: Vec<Row<'_, '_>>,
+    target: String,
+) -> Decision {
+    let var = codegen.new_tmp("v");
+
+    let mut types = Vec::new();
+    for row in &rows {
+        if let Some((_, Test::Type(type_))) = row_test(codegen, row, &target) {
+            if !types.contains(&type_) {
+                types.push(type_);
+            }
+        }
+    }
+
+    let mut cases = Vec::with_capacity(types.len());
+    for type_ in types {
+        let mut case_rows = Vec::with_capacity(rows.len());
+
+        for row in &rows {
+            match row_test(codegen, row, &target) {
+                Some((idx, Test::Type(row_type))) => {
+                    if row_type != type_ {
+                        continue;
+                    }
+
+                    let mut row = row.clone();
+                    let (_, pattern) = row.tests.remove(idx);
+
+                    match pattern {
+                        ast::Pattern::Type(type_pattern) => {
+                            if let Some(name) = &type_pattern.name {
+                                row.bindings.push((name.as_ref().to_string(), var.clone()));
+                            }
+                        }
+                        ast::Pattern::Ctor(ctor_pattern) => {
+                            // Fields are tested in place of the constructor
+                            let ctor = ctor_pattern.name.as_ref();
+                            let fields =
+                                ctor_pattern.args.iter().enumerate().map(|(field, arg)| {
+                                    (codegen.ctor_field(&var, ctor, field), arg)
+                                });
+                            row.tests.splice(idx..idx, fields);
+                        }
+                        // Variant without fields
+                        ast::Pattern::Bind(_) => {}
+                        _ => unreachable!("only type tests have a Go type"),
+                    }
+
+                    push_row(codegen, row, &mut case_rows);
+                }
+                // Value tests on the same target are left for later
+                Some((_, Test::Value(_))) | None => case_rows.push(row.clone()),
+            }
+        }
+
+        cases.push((type_, compile(codegen, case_rows)));
+    }
+
+    let default_rows = rows
+        .iter()
+        .filter(|row| !matches!(row_test(codegen, row, &target), Some((_, Test::Type(_)))))
+        .cloned()
+        .collect();
+    let default = compile(codegen, default_rows);
+
+    // A case with several types doesn't narrow the value, so only merge if it isn't used
+    let cases = share_cases(cases, &default, |decision| !decision.mentions(&var));
+
+    // Go requires the variable to be used in at least one case
+    let var = cases
+        .iter()
+        .any(|(_, decision)| decision.mentions(&var))
+        .then_some(var);
+
+    Decision::TypeSwitch {
+        target,
+        var,
+        cases,
+        default: Box::new(default),
+    }
+}
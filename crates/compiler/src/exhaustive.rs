@@ -0,0 +1,376 @@
+// Match exhaustiveness and reachability checking. This is based on the "usefulness" algorithm
+// from "Warnings for pattern matching" (Maranget, 2007): a pattern is useful with respect to a
+// list of earlier patterns if there is a value that it matches but none of the earlier ones do.
+// A match is exhaustive if a wildcard is not useful after all the arms, and an arm is
+// unreachable if it is not useful after the arms before it.
+
+use std::collections::HashMap;
+use std::fmt;
+
+// Don't flood the user with missing patterns
+const MAX_WITNESSES: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Ctor {
+    Bool(bool),
+    Int(i32),
+    // Decoded values, so different escapes of the same value are the same
+    String(String),
+    Char(char),
+    // Variant of a known enum
+    Variant(String),
+    // Type test - there are always more types
+    Type(String),
+    // Constructor of a type we know nothing about
+    Named(String),
+}
+
+#[derive(Clone, Debug)]
+enum Pat {
+    Wild,
+    Ctor(Ctor, Vec<Pat>),
+    Or(Vec<Pat>),
+}
+
+impl fmt::Display for Pat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pat::Wild => f.write_str("_"),
+            Pat::Ctor(ctor, args) => {
+                match ctor {
+                    Ctor::Bool(b) => write!(f, "{b}")?,
+                    Ctor::Int(i) => write!(f, "{i}")?,
+                    Ctor::String(s) => write!(f, "{s:?}")?,
+                    Ctor::Char(c) => write!(f, "{c:?}")?,
+                    Ctor::Variant(s) | Ctor::Named(s) => f.write_str(s)?,
+                    Ctor::Type(name) => write!(f, "_: {name}")?,
+                }
+
+                if !args.is_empty() {
+                    f.write_str("(")?;
+                    for (idx, arg) in args.iter().enumerate() {
+                        if idx > 0 {
+                            f.write_str(", ")?;
+                        }
+                        write!(f, "{arg}")?;
+                    }
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            Pat::Or(alts) => {
+                for (idx, alt) in alts.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{alt}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Result of checking a single match
+#[derive(Debug, Default, PartialEq)]
+pub struct MatchReport {
+    // Patterns (as source text) of values not matched by any arm. Empty if exhaustive
+    pub missing: Vec<String>,
+    // True if there were more missing patterns than listed
+    pub more_missing: bool,
+    // Patterns (as source text) of arms, or alternatives within an arm, that can never match
+    pub unreachable: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Exhaustiveness {
+    // Variant name -> (enum name, # of fields)
+    variants: HashMap<String, (String, usize)>,
+    // Enum name -> variant names (in declaration order)
+    enums: HashMap<String, Vec<String>>,
+}
+
+impl Exhaustiveness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Registers an enum so matches on it can be proven exhaustive. A struct is registered as an
+    // enum with a single variant of the same name
+    pub fn add_enum(&mut self, name: &str, variants: &[(&str, usize)]) {
+        for (variant, arity) in variants {
+            self.variants
+                .insert(variant.to_string(), (name.to_string(), *arity));
+        }
+        self.enums.insert(
+            name.to_string(),
+            variants.iter().map(|(name, _)| name.to_string()).collect(),
+        );
+    }
+
+    pub fn check(&self, arms: &[ast::MatchArm<'_>]) -> MatchReport {
+        let mut report = MatchReport::default();
+        let mut rows: Vec<Vec<Pat>> = Vec::with_capacity(arms.len());
+
+        for arm in arms {
+            let pat = self.lower(&arm.pattern);
+
+            if !self.is_useful(&rows, std::slice::from_ref(&pat)) {
+                report.unreachable.push(pat.to_string());
+            } else if let Pat::Or(alts) = &pat {
+                // The arm as a whole can match, but maybe not every alternative can
+                for (idx, alt) in alts.iter().enumerate() {
+                    let mut prev = rows.clone();
+                    prev.extend(alts[..idx].iter().map(|alt| vec![alt.clone()]));
+
+                    if !self.is_useful(&prev, std::slice::from_ref(alt)) {
+                        report.unreachable.push(alt.to_string());
+                    }
+                }
+            }
+
+            rows.push(vec![pat]);
+        }
+
+        let missing = self.missing(&rows, 1);
+        report.more_missing = missing.len() > MAX_WITNESSES;
+        report.missing = missing
+            .into_iter()
+            .take(MAX_WITNESSES)
+            .map(|mut witness| witness.remove(0).to_string())
+            .collect();
+        report
+    }
+
+    fn lower(&self, pattern: &ast::Pattern<'_>) -> Pat {
+        match pattern {
+            ast::Pattern::Wildcard => Pat::Wild,
+            // A bare name is a variant with no fields if one exists, otherwise a binding
+            ast::Pattern::Bind(name) => match self.variants.get(name.as_ref()) {
+                Some((_, 0)) => Pat::Ctor(Ctor::Variant(name.as_ref().to_string()), vec![]),
+                _ => Pat::Wild,
+            },
+            ast::Pattern::IntLit(int_lit) => Pat::Ctor(Ctor::Int(int_lit.value), vec![]),
+            ast::Pattern::StringLit(string_lit) => {
+                Pat::Ctor(Ctor::String(string_lit.value().to_string()), vec![])
+            }
+            ast::Pattern::CharLit(char_lit) => Pat::Ctor(Ctor::Char(char_lit.value()), vec![]),
+            ast::Pattern::BoolLit(bool_lit) => Pat::Ctor(Ctor::Bool(bool_lit.0), vec![]),
+            ast::Pattern::Alt(patterns) => {
+                Pat::Or(patterns.iter().map(|pattern| self.lower(pattern)).collect())
+            }
+            ast::Pattern::As(pattern, _) => self.lower(pattern),
+            ast::Pattern::Type(type_pattern) => Pat::Ctor(
+                Ctor::Type(type_pattern.pattern_type.as_ref().to_string()),
+                vec![],
+            ),
+            ast::Pattern::Ctor(ctor_pattern) => {
+                let name = ctor_pattern.name.as_ref().to_string();
+                let ctor = if self.variants.contains_key(&name) {
+                    Ctor::Variant(name)
+                } else {
+                    Ctor::Named(name)
+                };
+                let args = ctor_pattern
+                    .args
+                    .iter()
+                    .map(|pattern| self.lower(pattern))
+                    .collect();
+                Pat::Ctor(ctor, args)
+            }
+        }
+    }
+
+    // All constructors of the type (with their # of fields) if there are a finite number of them
+    fn signature(&self, heads: &[(&Ctor, usize)]) -> Option<Vec<(Ctor, usize)>> {
+        let sig = match heads.first()?.0 {
+            Ctor::Bool(_) => vec![(Ctor::Bool(true), 0), (Ctor::Bool(false), 0)],
+            Ctor::Variant(name) => {
+                let (enum_name, _) = self.variants.get(name)?;
+                self.enums[enum_name]
+                    .iter()
+                    .map(|name| (Ctor::Variant(name.clone()), self.variants[name].1))
+                    .collect()
+            }
+            _ => return None,
+        };
+
+        // If anything else is being tested for, then this isn't really a finite type
+        if heads
+            .iter()
+            .all(|head| sig.iter().any(|(ctor, _)| ctor == head.0))
+        {
+            Some(sig)
+        } else {
+            None
+        }
+    }
+
+    fn head_ctors<'a>(rows: &'a [Vec<Pat>], heads: &mut Vec<(&'a Ctor, usize)>) {
+        fn collect<'a>(pat: &'a Pat, heads: &mut Vec<(&'a Ctor, usize)>) {
+            match pat {
+                Pat::Wild => {}
+                Pat::Ctor(ctor, args) => {
+                    if !heads.iter().any(|(head, _)| *head == ctor) {
+                        heads.push((ctor, args.len()));
+                    }
+                }
+                Pat::Or(alts) => alts.iter().for_each(|alt| collect(alt, heads)),
+            }
+        }
+
+        for row in rows {
+            collect(&row[0], heads);
+        }
+    }
+
+    // Rows that could match a value built with the constructor, with the constructor replaced by
+    // its fields
+    fn specialize(rows: &[Vec<Pat>], ctor: &Ctor, arity: usize) -> Vec<Vec<Pat>> {
+        fn push_row(row: &[Pat], ctor: &Ctor, arity: usize, out: &mut Vec<Vec<Pat>>) {
+            match &row[0] {
+                Pat::Wild => {
+                    let mut new_row = vec![Pat::Wild; arity];
+                    new_row.extend_from_slice(&row[1..]);
+                    out.push(new_row);
+                }
+                Pat::Ctor(head, args) if head == ctor && args.len() == arity => {
+                    let mut new_row = args.clone();
+                    new_row.extend_from_slice(&row[1..]);
+                    out.push(new_row);
+                }
+                Pat::Ctor(_, _) => {}
+                Pat::Or(alts) => {
+                    for alt in alts {
+                        let mut alt_row = vec![alt.clone()];
+                        alt_row.extend_from_slice(&row[1..]);
+                        push_row(&alt_row, ctor, arity, out);
+                    }
+                }
+            }
+        }
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            push_row(row, ctor, arity, &mut out);
+        }
+        out
+    }
+
+    // Rows that could match a value built with a constructor not tested for by any row
+    fn default_rows(rows: &[Vec<Pat>]) -> Vec<Vec<Pat>> {
+        fn push_row(row: &[Pat], out: &mut Vec<Vec<Pat>>) {
+            match &row[0] {
+                Pat::Wild => out.push(row[1..].to_vec()),
+                Pat::Ctor(_, _) => {}
+                Pat::Or(alts) => {
+                    for alt in alts {
+                        let mut alt_row = vec![alt.clone()];
+                        alt_row.extend_from_slice(&row[1..]);
+                        push_row(&alt_row, out);
+                    }
+                }
+            }
+        }
+
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            push_row(row, &mut out);
+        }
+        out
+    }
+
+    fn is_useful(&self, rows: &[Vec<Pat>], row: &[Pat]) -> bool {
+        let Some((head, rest)) = row.split_first() else {
+            return rows.is_empty();
+        };
+
+        let with_fields = |fields: &[Pat]| {
+            let mut new_row = fields.to_vec();
+            new_row.extend_from_slice(rest);
+            new_row
+        };
+
+        match head {
+            Pat::Or(alts) => alts
+                .iter()
+                .any(|alt| self.is_useful(rows, &with_fields(std::slice::from_ref(alt)))),
+            Pat::Ctor(ctor, args) => self.is_useful(
+                &Self::specialize(rows, ctor, args.len()),
+                &with_fields(args),
+            ),
+            Pat::Wild => {
+                let mut heads = Vec::new();
+                Self::head_ctors(rows, &mut heads);
+
+                match self.signature(&heads) {
+                    Some(sig) if sig.len() == heads.len() => sig.iter().any(|(ctor, arity)| {
+                        self.is_useful(
+                            &Self::specialize(rows, ctor, *arity),
+                            &with_fields(&vec![Pat::Wild; *arity]),
+                        )
+                    }),
+                    _ => self.is_useful(&Self::default_rows(rows), rest),
+                }
+            }
+        }
+    }
+
+    // Returns example rows of 'width' patterns not matched by any of the rows
+    fn missing(&self, rows: &[Vec<Pat>], width: usize) -> Vec<Vec<Pat>> {
+        if width == 0 {
+            return if rows.is_empty() {
+                vec![vec![]]
+            } else {
+                vec![]
+            };
+        }
+
+        let mut heads = Vec::new();
+        Self::head_ctors(rows, &mut heads);
+        let sig = self.signature(&heads);
+
+        match sig {
+            // Every constructor is tested for, so the missing values must be within them
+            Some(sig) if sig.len() == heads.len() => {
+                let mut witnesses = Vec::new();
+                for (ctor, arity) in sig {
+                    let specialized = Self::specialize(rows, &ctor, arity);
+                    for mut witness in self.missing(&specialized, arity + width - 1) {
+                        let rest = witness.split_off(arity);
+                        let mut new_witness = vec![Pat::Ctor(ctor.clone(), witness)];
+                        new_witness.extend(rest);
+                        witnesses.push(new_witness);
+                    }
+                }
+                witnesses
+            }
+            sig => {
+                let witnesses = self.missing(&Self::default_rows(rows), width - 1);
+                if witnesses.is_empty() {
+                    return witnesses;
+                }
+
+                // Report the constructors that aren't tested for by name if we know them
+                let heads: Vec<_> = match sig {
+                    Some(sig) if !heads.is_empty() => sig
+                        .into_iter()
+                        .filter(|(ctor, _)| !heads.iter().any(|(head, _)| *head == ctor))
+                        .map(|(ctor, arity)| Pat::Ctor(ctor, vec![Pat::Wild; arity]))
+                        .collect(),
+                    _ => vec![Pat::Wild],
+                };
+
+                let mut new_witnesses = Vec::with_capacity(heads.len() * witnesses.len());
+                for witness in witnesses {
+                    for head in &heads {
+                        let mut new_witness = vec![head.clone()];
+                        new_witness.extend_from_slice(&witness);
+                        new_witnesses.push(new_witness);
+                    }
+                }
+                new_witnesses
+            }
+        }
+    }
+}
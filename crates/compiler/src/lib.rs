@@ -1,7 +1,11 @@
+use std::collections::HashSet;
+
 use lalrpop_util::{lalrpop_mod, ParseError};
 use lexer::{Lexer, TokenType};
 
+pub mod check;
 pub mod codegen_go;
+mod exhaustive;
 #[cfg(test)]
 mod test;
 
@@ -13,3 +17,50 @@ pub fn compile(input: &str) -> ParseResult<Vec<ast::Decl<'_>>> {
     lalrpop_mod!(interop);
     interop::FileParser::new().parse(input, lexer)
 }
+
+const PRELUDE: &str = include_str!("prelude.int");
+
+// Names a declaration brings into scope (an enum's variants are in scope too)
+fn declared_names<'a>(decl: &'a ast::Decl<'_>) -> Vec<&'a str> {
+    match decl {
+        ast::Decl::Func(func) => vec![func.name.as_ref()],
+        ast::Decl::Struct(struct_) => vec![struct_.name.as_ref()],
+        ast::Decl::Enum(enum_) => std::iter::once(enum_.name.as_ref())
+            .chain(enum_.variants.iter().map(|variant| variant.name.as_ref()))
+            .collect(),
+        ast::Decl::Interface(interface) => vec![interface.name.as_ref()],
+        ast::Decl::Type(type_decl) => vec![type_decl.name.as_ref()],
+        ast::Decl::Impl(_) => Vec::new(),
+        ast::Decl::Let(let_) => match &let_.target {
+            ast::LetTarget::Ident(name) => vec![name.as_ref()],
+            ast::LetTarget::SelfField(_) => Vec::new(),
+        },
+        ast::Decl::Const(const_) => vec![const_.name.as_ref()],
+    }
+}
+
+// The prelude declarations the file doesn't shadow by declaring one of their names itself
+// (shadowing any name of an enum shadows all of it, impls included)
+pub fn prelude(decls: &[ast::Decl<'_>]) -> Vec<ast::Decl<'static>> {
+    let prelude = compile(PRELUDE).expect("prelude should parse");
+    let declared: HashSet<_> = decls.iter().flat_map(declared_names).collect();
+    let shadowed: HashSet<_> = prelude
+        .iter()
+        .map(declared_names)
+        .filter(|names| names.iter().any(|name| declared.contains(name)))
+        .flatten()
+        .map(str::to_string)
+        .collect();
+
+    prelude
+        .into_iter()
+        .filter(|decl| match decl {
+            ast::Decl::Impl(impl_) => std::iter::once(&impl_.name)
+                .chain(&impl_.interface)
+                .all(|name| !shadowed.contains(name.as_ref())),
+            decl => declared_names(decl)
+                .iter()
+                .all(|name| !shadowed.contains(*name)),
+        })
+        .collect()
+}
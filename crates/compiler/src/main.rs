@@ -1,7 +1,8 @@
 use std::{env, fs};
 
+use compiler::check;
 use compiler::codegen_go;
-use compiler::compile;
+use compiler::{compile, prelude};
 
 fn main() {
     // TODO: Replace with clap
@@ -12,10 +13,19 @@ fn main() {
         Ok(ast) => {
             //println!("{ast:#?}");
 
-            let mut codegen = codegen_go::GoCodegen::new();
-            let code = codegen.gen_code(&ast);
+            let prelude = prelude(&ast);
+            let analysis = check::analyze(&prelude, &ast);
+            for diagnostic in &analysis.diagnostics {
+                eprintln!("{diagnostic}");
+            }
+            if check::has_errors(&analysis.diagnostics) {
+                std::process::exit(1);
+            }
+
+            let mut codegen = codegen_go::GoCodegen::with_annotations(analysis.annotations);
+            let code = codegen.gen_code(&prelude, &ast);
             println!("{code}");
         }
-        e @ _ => println!("Parsing failed: {e:#?}"),
+        e => println!("Parsing failed: {e:#?}"),
     }
 }
@@ -72,6 +72,55 @@ fn func_no_parens() {
     assert!(actual.is_err());
 }
 
+#[test]
+fn func_type_params() {
+    let src = r"
+        func my_func[K, V: Show](m: map[K, V], key: K) -> list[V]:
+            m[key]
+        end";
+    let actual = func_parser(src).expect("func node");
+    assert_eq!(actual.type_params.len(), 2);
+    assert_eq!(
+        actual.type_params[1].bound,
+        Some(ast::Type::Simple(ast::Ident::from_str("Show")))
+    );
+    assert_eq!(actual.args[0].arg_type.to_string(), "map[K, V]");
+}
+
+#[test]
+fn func_generic_return_type() {
+    let src = r"
+        func my_func() -> Pair[list[Int], Int]:
+            123
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(Some(ret_type), _) = &actual.body else {
+        panic!("expected a block body: {:?}", actual.body);
+    };
+    assert_eq!(ret_type.to_string(), "Pair[list[Int], Int]");
+}
+
+#[test]
+fn func_arrow_index() {
+    let src = r"
+        func my_func() -> items[0]";
+    let actual = func_parser(src).expect("func node");
+    assert!(matches!(
+        actual.body,
+        ast::FuncBody::Expr(ast::SimpleExpr::Index(_))
+    ));
+}
+
+#[test]
+fn func_bad_return_type() {
+    let src = r"
+        func my_func() -> items():
+            123
+        end";
+    let actual = func_parser(src);
+    assert!(actual.is_err());
+}
+
 // *** function args ***
 
 fn func_args_parser(src: &str) -> ParseResult<Vec<ast::FuncArg<'_>>> {
@@ -93,9 +142,34 @@ fn func_args_comma_list_one_type_default_val() {
     let _actual = func_args_parser(src).expect("function arg node");
 }
 
+#[test]
+fn func_args_grouped() {
+    let src = r"a, b: Int, c: Str";
+    let actual = func_args_parser(src).expect("function arg node");
+    let names: Vec<_> = actual.iter().map(|arg| arg.name.as_ref()).collect();
+    assert_eq!(names, ["a", "b", "c"]);
+    assert_eq!(actual[0].arg_type, actual[1].arg_type);
+    assert!(actual.iter().all(|arg| arg.default_val.is_none()));
+}
+
+#[test]
+fn func_args_grouped_default_val_on_last() {
+    let src = r"a: Int, b, c: Int = 1, d: Str = 2";
+    let actual = func_args_parser(src).expect("function arg node");
+    let defaults: Vec<_> = actual.iter().map(|arg| arg.default_val.is_some()).collect();
+    assert_eq!(defaults, [false, false, true, true]);
+}
+
+#[test]
+fn func_args_grouped_after_default_val() {
+    let src = r"a: Int = 1, b, c: Int = 2";
+    let actual = func_args_parser(src);
+    assert!(actual.is_err());
+}
+
 #[test]
 fn func_args_missing_type() {
-    let src = r"a, b: Int";
+    let src = r"a, b";
     let actual = func_args_parser(src);
     assert!(actual.is_err());
 }
@@ -328,3 +402,2766 @@ fn bool_cond_compound() {
     let src = r"not a or b and c";
     let _actual = expr_parser(src).expect("expr node");
 }
+
+#[test]
+fn binary_op_precedence() {
+    let src = r"a + b * c == d";
+    let actual = expr_parser(src).expect("expr node");
+    let ast::Expr::Simple(ast::SimpleExpr::BinaryOp(eq)) = actual else {
+        panic!("expected a binary op: {actual:?}");
+    };
+    assert_eq!(eq.op, ast::BinaryOperator::Equal);
+    let ast::SimpleExpr::BinaryOp(add) = &eq.lhs else {
+        panic!("expected a binary op: {:?}", eq.lhs);
+    };
+    assert_eq!(add.op, ast::BinaryOperator::Add);
+    assert!(
+        matches!(&add.rhs, ast::SimpleExpr::BinaryOp(mul) if mul.op == ast::BinaryOperator::Multiply)
+    );
+}
+
+#[test]
+fn binary_op_chained_comparison() {
+    let src = r"a == b != c";
+    let actual = expr_parser(src);
+    assert!(actual.is_err());
+}
+
+#[test]
+fn binary_op_under_bool_cond() {
+    let src = r"not a != b or c - 1 == d / 2";
+    let _actual = expr_parser(src).expect("expr node");
+}
+
+#[test]
+fn index_basic() {
+    let src = r"test[a + 1][0].field";
+    let _actual = expr_parser(src).expect("expr node");
+}
+
+#[test]
+fn string_char_values() {
+    let value = |src| match expr_parser(src).expect("expr node") {
+        ast::Expr::Simple(ast::SimpleExpr::StringLit(string_lit)) => string_lit.value().to_string(),
+        _ => panic!("expected string node"),
+    };
+    assert_eq!(value(r#""a\tb\"\\\{\u0000e9\x41""#), "a\tb\"\\{éA");
+    assert_eq!(value(r#""plain""#), "plain");
+
+    let value = |src| match expr_parser(src).expect("expr node") {
+        ast::Expr::Simple(ast::SimpleExpr::CharLit(char_lit)) => char_lit.value(),
+        _ => panic!("expected char node"),
+    };
+    assert_eq!(value(r"'\o101'"), 'A');
+    assert_eq!(value(r"'\b01000001'"), 'A');
+    assert_eq!(value(r"'\''"), '\'');
+    assert_eq!(value("'é'"), 'é');
+}
+
+#[test]
+fn string_raw() {
+    let src = r#"``"a\d+"{x}"``"#;
+    let actual = expr_parser(src).expect("expr node");
+    let ast::Expr::Simple(ast::SimpleExpr::StringLit(string_lit)) = actual else {
+        panic!("expected string node");
+    };
+    assert_eq!(string_lit.raw(), Some(r#"a\d+"{x}"#));
+}
+
+#[test]
+fn string_interpolated() {
+    let src = r#""a {x} b {f(y, "c")}""#;
+    let actual = expr_parser(src).expect("expr node");
+    let ast::Expr::Simple(ast::SimpleExpr::InterpolatedString(string)) = actual else {
+        panic!("expected interpolated string node");
+    };
+    let strings: Vec<_> = string
+        .strings
+        .iter()
+        .map(|string| string.unparsed.as_ref())
+        .collect();
+    assert_eq!(strings, vec![r#""a ""#, r#"" b ""#, r#""""#]);
+    assert!(matches!(string.exprs[0], ast::SimpleExpr::Ident(_)));
+    assert!(matches!(string.exprs[1], ast::SimpleExpr::Call(_)));
+}
+
+// *** Comprehensions ***
+
+#[test]
+fn comprehension_list() {
+    let src = r"[x * 2 <- for x in range(start = 1, stop = 10) if x != 0]";
+    let actual = expr_parser(src).expect("comprehension node");
+    let ast::Expr::Simple(ast::SimpleExpr::Comprehension(comprehension)) = actual else {
+        panic!("expected comprehension node");
+    };
+    assert!(matches!(
+        comprehension.kind,
+        ast::ComprehensionKind::List(ast::SimpleExpr::BinaryOp(_))
+    ));
+    assert_eq!(comprehension.name, ast::Ident::from_str("x"));
+    assert!(matches!(comprehension.source, ast::SimpleExpr::Call(_)));
+    assert!(comprehension.cond.is_some());
+}
+
+#[test]
+fn comprehension_kinds() {
+    let kind = |src| match expr_parser(src).expect("comprehension node") {
+        ast::Expr::Simple(ast::SimpleExpr::Comprehension(comprehension)) => comprehension.kind,
+        _ => panic!("expected comprehension node"),
+    };
+
+    assert!(matches!(
+        kind("(x <- for x in xs)"),
+        ast::ComprehensionKind::Iter(_)
+    ));
+    assert!(matches!(
+        kind("[x <- for x in xs]"),
+        ast::ComprehensionKind::List(_)
+    ));
+    assert!(matches!(
+        kind("{x: f(x) <- for x in xs}"),
+        ast::ComprehensionKind::Map(_, ast::SimpleExpr::Call(_))
+    ));
+    assert!(matches!(
+        kind("{|x <- for x in xs|}"),
+        ast::ComprehensionKind::Set(_)
+    ));
+
+    // Still just parens
+    assert!(matches!(
+        expr_parser("(x)").expect("expr node"),
+        ast::Expr::Simple(ast::SimpleExpr::Expr(_))
+    ));
+}
+
+#[test]
+fn comprehension_missing_for() {
+    assert!(expr_parser("[x <- xs]").is_err());
+    assert!(expr_parser("{x <- for x in xs}").is_err());
+}
+
+// *** Match ***
+
+#[test]
+fn match_basic() {
+    let src = r"
+        match abc with:
+            1, 2 -> true
+            _    -> false
+        end";
+    let _actual = expr_parser(src).expect("match node");
+}
+
+#[test]
+fn match_literals() {
+    let src = r#"
+        match abc with:
+            "a"   -> 1
+            'b'   -> 2
+            true  -> 3
+            _     -> 4
+        end"#;
+    let _actual = expr_parser(src).expect("match node");
+}
+
+#[test]
+fn match_bindings() {
+    let src = r"
+        match abc with:
+            1 as x -> x
+            y      -> y
+        end";
+    let actual = expr_parser(src).expect("match node");
+    let ast::Expr::Match(match_) = actual else {
+        panic!("expected match node");
+    };
+    assert_eq!(
+        match_.arms[0].pattern,
+        ast::Pattern::As(
+            Box::new(ast::Pattern::IntLit(ast::IntLit { value: 1 })),
+            ast::Ident::from_str("x")
+        )
+    );
+    assert_eq!(
+        match_.arms[1].pattern,
+        ast::Pattern::Bind(ast::Ident::from_str("y"))
+    );
+}
+
+#[test]
+fn match_wildcard() {
+    let src = r"
+        match abc with:
+            _ -> 1
+        end";
+    let actual = expr_parser(src).expect("match node");
+    let ast::Expr::Match(match_) = actual else {
+        panic!("expected match node");
+    };
+    assert_eq!(match_.arms[0].pattern, ast::Pattern::Wildcard);
+}
+
+#[test]
+fn match_no_arms() {
+    let src = r"
+        match abc with:
+        end";
+    let actual = expr_parser(src);
+    assert!(actual.is_err());
+}
+
+#[test]
+fn match_missing_with() {
+    let src = r"
+        match abc:
+            _ -> 1
+        end";
+    let actual = expr_parser(src);
+    assert!(actual.is_err());
+}
+
+#[test]
+fn match_types() {
+    let src = r"
+        match a with:
+            Some(x: int) -> x
+            b: int       -> b
+            _: str       -> 1
+            _ as y       -> y
+        end";
+    let actual = expr_parser(src).expect("match node");
+    let ast::Expr::Match(match_) = actual else {
+        panic!("expected match node");
+    };
+    assert_eq!(
+        match_.arms[0].pattern,
+        ast::Pattern::Ctor(ast::CtorPattern {
+            name: ast::Ident::from_str("Some"),
+            args: vec![ast::Pattern::Type(ast::TypePattern {
+                name: Some(ast::Ident::from_str("x")),
+                pattern_type: ast::Type::Simple(ast::Ident::from_str("int")),
+            })],
+        })
+    );
+    assert_eq!(
+        match_.arms[2].pattern,
+        ast::Pattern::Type(ast::TypePattern {
+            name: None,
+            pattern_type: ast::Type::Simple(ast::Ident::from_str("str")),
+        })
+    );
+}
+
+#[test]
+fn match_nested_ctors() {
+    let src = r"
+        match a with:
+            Pair(Some(1 as x), _), None() -> 1
+            _                             -> 2
+        end";
+    let _actual = expr_parser(src).expect("match node");
+}
+
+#[test]
+fn match_nested_alternatives() {
+    let src = r"
+        match a with:
+            Some(1, 2) -> 1
+            Some(1 as x, 2, 3 as y) -> 2
+        end";
+    let _actual = expr_parser(src).expect("match node");
+
+    let src = r"
+        match a with:
+            Some(1 -> 1
+        end";
+    let actual = expr_parser(src);
+    assert!(actual.is_err());
+}
+
+// *** Struct ***
+
+fn struct_parser(src: &str) -> ParseResult<ast::Struct<'_>> {
+    let lexer = Lexer::new(src, false, false);
+
+    lalrpop_mod!(interop);
+    interop::StructParser::new().parse(src, lexer)
+}
+
+fn enum_parser(src: &str) -> ParseResult<ast::Enum<'_>> {
+    let lexer = Lexer::new(src, false, false);
+
+    lalrpop_mod!(interop);
+    interop::EnumParser::new().parse(src, lexer)
+}
+
+fn interface_parser(src: &str) -> ParseResult<ast::Interface<'_>> {
+    let lexer = Lexer::new(src, false, false);
+
+    lalrpop_mod!(interop);
+    interop::InterfaceParser::new().parse(src, lexer)
+}
+
+fn impl_parser(src: &str) -> ParseResult<ast::Impl<'_>> {
+    let lexer = Lexer::new(src, false, false);
+
+    lalrpop_mod!(interop);
+    interop::ImplParser::new().parse(src, lexer)
+}
+
+#[test]
+fn struct_fields() {
+    let src = r"
+        struct ABC:
+            test : int
+            test2: str
+        end";
+    let actual = struct_parser(src).expect("struct node");
+    assert_eq!(actual.fields.len(), 2);
+    assert_eq!(actual.fields[1].field_type.as_ref(), "str");
+}
+
+#[test]
+fn struct_type_params() {
+    let src = r"
+        struct Pair[K: Show, V]:
+            key: K
+            value: list[V]
+        end";
+    let actual = struct_parser(src).expect("struct node");
+    assert_eq!(actual.type_params.len(), 2);
+    assert_eq!(actual.fields[1].field_type.to_string(), "list[V]");
+}
+
+#[test]
+fn struct_no_fields() {
+    let src = r"
+        struct ABC:
+        end";
+    let _actual = struct_parser(src).expect_err("parse error");
+}
+
+#[test]
+fn enum_variants() {
+    let src = r"
+        enum Result:
+            Ok(int)
+            Error(str)
+            Pair(int, str,)
+            Unknown
+        end";
+    let actual = enum_parser(src).expect("enum node");
+    let fields: Vec<_> = actual
+        .variants
+        .iter()
+        .map(|variant| variant.fields.len())
+        .collect();
+    assert_eq!(fields, vec![1, 1, 2, 0]);
+}
+
+#[test]
+fn enum_values() {
+    let src = r"
+        enum Suites:
+            Hearts = 0
+            Spades
+            Diamonds = 5
+            Clubs
+        end";
+    let actual = enum_parser(src).expect("enum node");
+    assert!(actual.is_c_like());
+    assert_eq!(actual.values(), vec![0, 1, 5, 6]);
+}
+
+#[test]
+fn enum_type_params() {
+    let src = r"
+        enum Option[T]:
+            Some(T)
+            None
+        end";
+    let actual = enum_parser(src).expect("enum node");
+    assert_eq!(actual.type_params[0].name.as_ref(), "T");
+    assert!(!actual.is_c_like());
+}
+
+#[test]
+fn enum_empty_payload() {
+    let src = r"
+        enum Result:
+            Ok()
+        end";
+    let _actual = enum_parser(src).expect_err("parse error");
+}
+
+#[test]
+fn interface_methods() {
+    let src = r"
+        interface shape:
+            area() -> int
+            scale(int, int,) -> shape
+            draw()
+        end";
+    let actual = interface_parser(src).expect("interface node");
+    assert_eq!(actual.methods.len(), 3);
+    assert_eq!(actual.methods[1].arg_types.len(), 2);
+    assert_eq!(actual.methods[2].ret_type, None);
+}
+
+#[test]
+fn interface_empty() {
+    let src = r"
+        interface anything:
+        end";
+    let _actual = interface_parser(src).expect("interface node");
+}
+
+#[test]
+fn impl_interface_for() {
+    let src = r"
+        impl equal for DayOfWeek:
+            func __equal__(a: any) -> bool:
+                false
+            end
+        end";
+    let actual = impl_parser(src).expect("impl node");
+    assert_eq!(actual.name.as_ref(), "DayOfWeek");
+    assert_eq!(actual.interface.expect("interface").as_ref(), "equal");
+}
+
+#[test]
+fn impl_new_and_method() {
+    let src = r"
+        impl ABC:
+            func new(test: int, test2: str):
+                let self.test = test
+                let var self.test2 = test2
+            end
+
+            func test() -> self.test
+        end";
+    let actual = impl_parser(src).expect("impl node");
+    assert_eq!(actual.funcs.len(), 2);
+}
+
+#[test]
+fn impl_type_params() {
+    let src = r"
+        impl Show for Pair[K, V]:
+            func show() -> str:
+                self.key.show()
+            end
+        end";
+    let actual = impl_parser(src).expect("impl node");
+    let names: Vec<_> = actual
+        .type_params
+        .iter()
+        .map(|name| name.as_ref())
+        .collect();
+    assert_eq!(names, vec!["K", "V"]);
+}
+
+#[test]
+fn let_typed() {
+    let src = r"
+        func my_func():
+            let x: int = 5
+            x
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(_, block) = actual.body else {
+        panic!("expected block body");
+    };
+    assert!(matches!(
+        &block.stmt_or_exprs[0],
+        ast::StmtOrExpr::Let(ast::Let {
+            target: ast::LetTarget::Ident(_),
+            mutable: false,
+            let_type: Some(_),
+            ..
+        })
+    ));
+}
+
+// *** Try ***
+
+#[test]
+fn try_expr() {
+    let src = r"
+        func my_func():
+            let x = try parse(s) catch as e:
+                e
+            end
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(_, block) = actual.body else {
+        panic!("expected block body");
+    };
+    let ast::StmtOrExpr::Let(let_) = &block.stmt_or_exprs[0] else {
+        panic!("expected let node");
+    };
+    let ast::SimpleExpr::Try(try_) = &let_.value else {
+        panic!("expected try node");
+    };
+    assert!(matches!(
+        try_.body,
+        ast::TryBody::Expr(ast::SimpleExpr::Call(_))
+    ));
+    assert_eq!(try_.err_name, Some(ast::Ident::from_str("e")));
+    assert_eq!(try_.catch_body.stmt_or_exprs.len(), 1);
+}
+
+#[test]
+fn try_block() {
+    let src = r"
+        func my_func():
+            try:
+                z <- parse(s)
+                print(z)
+                z
+            catch:
+                err
+            end
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(_, block) = actual.body else {
+        panic!("expected block body");
+    };
+    let ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::Try(try_))) =
+        &block.stmt_or_exprs[0]
+    else {
+        panic!("expected try node");
+    };
+    let ast::TryBody::Block(try_block) = &try_.body else {
+        panic!("expected try block");
+    };
+    assert!(matches!(
+        &try_block.stmt_or_exprs[0],
+        ast::StmtOrExpr::Unwrap(ast::Unwrap {
+            value: ast::SimpleExpr::Call(_),
+            ..
+        })
+    ));
+    assert_eq!(try_block.stmt_or_exprs.len(), 3);
+    assert_eq!(try_.err_name, None);
+}
+
+#[test]
+fn try_missing_catch() {
+    let src = r"
+        func my_func():
+            try:
+                z <- parse(s)
+            end
+        end";
+    assert!(func_parser(src).is_err());
+}
+
+// *** With ***
+
+#[test]
+fn with_as() {
+    let src = r"
+        func my_func():
+            let x =
+                with mytype(123) as a:
+                    a.do_stuff()
+                    123
+                end
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(_, block) = actual.body else {
+        panic!("expected block body");
+    };
+    let ast::StmtOrExpr::Let(let_) = &block.stmt_or_exprs[0] else {
+        panic!("expected let node");
+    };
+    let ast::SimpleExpr::With(with) = &let_.value else {
+        panic!("expected with node");
+    };
+    assert!(matches!(with.context, ast::SimpleExpr::Call(_)));
+    assert_eq!(with.name, Some(ast::Ident::from_str("a")));
+    assert_eq!(with.body.stmt_or_exprs.len(), 2);
+}
+
+#[test]
+fn with_no_name() {
+    let src = r"
+        func my_func():
+            with lock:
+                print(1)
+            end
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(_, block) = actual.body else {
+        panic!("expected block body");
+    };
+    assert!(matches!(
+        &block.stmt_or_exprs[0],
+        ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::With(with)))
+            if with.name.is_none()
+    ));
+}
+
+// *** Coroutines ***
+
+#[test]
+fn coroutine_yield() {
+    let src = r"
+        func my_func() -> coroutine[Int, ()]:
+            yield 1
+            let x = yield
+            yield x + 1
+        end";
+    let actual = func_parser(src).expect("func node");
+    let ast::FuncBody::Block(Some(ret_type), block) = actual.body else {
+        panic!("expected block body with return type");
+    };
+    assert_eq!(ret_type.to_string(), "coroutine[Int, ()]");
+    assert!(matches!(
+        &block.stmt_or_exprs[0],
+        ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::Yield(value)))
+            if matches!(value.as_ref(), Some(ast::SimpleExpr::IntLit(_)))
+    ));
+    assert!(matches!(
+        &block.stmt_or_exprs[1],
+        ast::StmtOrExpr::Let(ast::Let { value: ast::SimpleExpr::Yield(value), .. })
+            if value.is_none()
+    ));
+    assert!(matches!(
+        &block.stmt_or_exprs[2],
+        ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::Yield(value)))
+            if matches!(value.as_ref(), Some(ast::SimpleExpr::BinaryOp(_)))
+    ));
+}
+
+#[test]
+fn yield_bare_only_in_stmt() {
+    assert!(expr_parser("f(yield)").is_err());
+    assert!(expr_parser("f(yield ())").is_ok());
+}
+
+// *** Async ***
+
+#[test]
+fn async_await() {
+    let src = r"
+        async func my_func() -> Int:
+            await fetch(1).value * 2
+        end";
+    let actual = func_parser(src).expect("func node");
+    assert!(actual.is_async);
+    let ast::FuncBody::Block(_, block) = actual.body else {
+        panic!("expected block body");
+    };
+    let ast::StmtOrExpr::Expr(ast::Expr::Simple(ast::SimpleExpr::BinaryOp(binary_op))) =
+        &block.stmt_or_exprs[0]
+    else {
+        panic!("expected binary op node");
+    };
+    // 'await' takes the whole primary, but binds tighter than '*'
+    assert!(matches!(
+        &binary_op.lhs,
+        ast::SimpleExpr::Await(future) if matches!(future.as_ref(), ast::SimpleExpr::Field(_))
+    ));
+}
+
+// *** Go codegen ***
+
+fn gen_go(src: &str) -> String {
+    let decls = crate::compile(src).expect("file node");
+    let prelude = crate::prelude(&decls);
+    let analysis = crate::check::analyze(&prelude, &decls);
+    let mut codegen = crate::codegen_go::GoCodegen::with_annotations(analysis.annotations);
+    codegen.gen_code(&prelude, &decls).to_string()
+}
+
+#[test]
+fn go_unused_lets() {
+    let src = r"
+func f(a: Int) -> Int:
+    let b = a + 1
+    let c = 2
+    let d = 3
+    if b then:
+        let e = d
+    end
+    b
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("\tb := a + 1\n\tc := 2\n\t_ = c\n\td := 3\n\tif b != 0 {\n"));
+    assert!(actual.contains("\t\te := d\n\t\t_ = e\n\t}\n"));
+    assert!(!actual.contains("_ = b"));
+    assert!(!actual.contains("_ = d"));
+}
+
+#[test]
+fn go_match_const_switch() {
+    let src = r"
+func my_func(abc: Int) -> Bool:
+    match abc with:
+        1, 2 -> true
+        2, 3 -> true
+        _    -> false
+        4    -> true
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("switch abc {\n\tcase 1, 2:\n\t\treturn true\n\tcase 3:\n"));
+    assert!(actual.contains("\tdefault:\n\t\treturn false\n\t}"));
+    assert!(!actual.contains("case 4"));
+}
+
+#[test]
+fn go_match_bindings() {
+    let src = r"
+func my_func(abc: Int) -> Int:
+    match f(abc) with:
+        1, 2 -> 5
+        3 as x -> x
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("\t__match0 := f(abc)\n\tswitch __match0 {\n\tcase 1, 2:\n"));
+    assert!(actual.contains("\tcase 3:\n\t\tx := __match0\n"));
+    assert!(actual.contains("\tdefault:\n\t\tpanic(\"no match arm matched\")\n"));
+}
+
+#[test]
+fn go_match_type_switch() {
+    let src = r"
+func abc(a: Any) -> Any:
+    match a with:
+        Just(x: int) -> x
+        b: int       -> b
+        _ as y       -> y
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("switch __v0 := any(a).(type) {\n\tcase Just:\n"));
+    assert!(actual.contains("\t\tswitch __v1 := any(__v0.F0).(type) {\n\t\tcase int:\n"));
+    assert!(actual.contains("\tcase int:\n\t\tb := __v0\n"));
+    assert!(actual.contains("\tdefault:\n\t\ty := a\n"));
+    assert!(!actual.contains("panic("));
+}
+
+#[test]
+fn go_match_decision_tree() {
+    let src = r"
+func eval(e: Any) -> Int:
+    match e with:
+        Add(Num(1), Num(y)) -> y
+        Add(Num(x), Num(2)) -> x
+        Add(_, _)           -> 3
+        Num(_)              -> 4
+        _                   -> 5
+    end
+end
+";
+    let actual = gen_go(src);
+    // Each part of the value is only tested once along any path
+    assert_eq!(actual.matches("any(e).(type)").count(), 1);
+    assert_eq!(actual.matches("any(__v0.F0).(type)").count(), 1);
+    assert_eq!(actual.matches("switch __v1.F0 {").count(), 1);
+    assert!(actual.contains("\tcase Num:\n\t\treturn 4\n\tdefault:\n\t\treturn 5\n"));
+}
+
+#[test]
+fn go_struct_and_methods() {
+    let src = r#"
+struct ABC:
+    test : Int
+    test2: str
+end
+
+impl ABC:
+    func test_len() -> Int:
+        self.test
+    end
+end
+
+func make() -> ABC:
+    ABC(test2="abc", test=1).test_len()
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains("type ABC struct {\n\ttest int\n\ttest2 string\n}"));
+    assert!(actual.contains("func (self ABC) test_len() int {\n\treturn self.test\n}"));
+    assert!(actual.contains("return ABC{test2: \"abc\", test: 1}.test_len()"));
+}
+
+#[test]
+fn go_struct_new() {
+    let src = r#"
+struct ABC:
+    test : Int
+end
+
+impl ABC:
+    func new(test: Int):
+        let self.test = test
+    end
+end
+
+func make() -> ABC:
+    ABC(5)
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "func NewABC(test int) ABC {\n\tvar self ABC\n\tself.test = test\n\treturn self\n}"
+    ));
+    assert!(actual.contains("return NewABC(5)"));
+}
+
+#[test]
+fn go_match_struct_fields() {
+    let src = r"
+struct Point:
+    x: Int
+    y: Int
+end
+
+func get_x(p: Point) -> Int:
+    match p with:
+        Point(x, _) -> x
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("\tcase Point:\n\t\tx := __v0.x\n"));
+}
+
+#[test]
+fn go_match_stmt_not_last() {
+    let src = r"
+func my_func(x: Int) -> Int:
+    match x with:
+        1 -> 10
+        _ -> print(x)
+    end
+    x
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "\tswitch x {\n\tcase 1:\n\t\t_ = 10\n\tdefault:\n\t\tprint(x)\n\t}\n\treturn x\n"
+    ));
+}
+
+#[test]
+fn go_match_let_value() {
+    let src = r"
+func my_func(x: Int) -> Int:
+    let y = (match x with:
+        1 -> 10
+        _ -> (match x with:
+            2 -> 20
+            _ -> 30
+        end)
+    end)
+    y
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "\tvar y int\n\tswitch x {\n\tcase 1:\n\t\ty = 10\n\tdefault:\n\t\tswitch x {\n\t\tcase 2:\n\t\t\ty = 20\n\t\tdefault:\n\t\t\ty = 30\n\t\t}\n\t}\n\treturn y\n"
+    ));
+}
+
+#[test]
+fn go_enum_sealed_interface() {
+    let src = r"
+enum Option:
+    Some(Int)
+    None
+end
+
+impl Option:
+    func is_some() -> Bool:
+        match self with:
+            Some(_) -> true
+            None    -> false
+        end
+    end
+end
+
+func wrap(a: Int) -> Option:
+    Some(a)
+end
+
+func nothing() -> Option:
+    None
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("type Option interface {\n\tisOption()\n\tis_some() bool\n}"));
+    assert!(actual.contains("type Some struct {\n\tF0 int\n}\n\nfunc (Some) isOption() {}"));
+    assert!(actual.contains("type None struct{}\n\nfunc (None) isOption() {}"));
+    assert!(actual.contains("func (self Some) is_some() bool {\n\tswitch any(self).(type) {"));
+    assert!(actual.contains("func (self None) is_some() bool {"));
+    assert!(actual.contains("\tcase None:\n\t\treturn false\n"));
+    assert!(actual.contains("return Some{F0: a}"));
+    assert!(actual.contains("return None{}"));
+}
+
+#[test]
+fn go_enum_consts() {
+    let src = r"
+enum DayOfWeek:
+    Sunday
+    Monday
+    Saturday
+end
+
+enum Suites:
+    Hearts = 2
+    Spades
+end
+
+impl DayOfWeek:
+    func is_weekend() -> Bool:
+        match self with:
+            Saturday, Sunday -> true
+            _                -> false
+        end
+    end
+end
+
+func parse_suite(s: str) -> Option[Suites]:
+    Suites.from_str(s)
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "type DayOfWeek int\n\nconst (\n\tSunday DayOfWeek = iota\n\tMonday\n\tSaturday\n)"
+    ));
+    assert!(actual.contains("const (\n\tHearts Suites = 2\n\tSpades Suites = 3\n)"));
+    assert!(actual.contains("\tcase Monday:\n\t\treturn \"Monday\"\n"));
+    assert!(actual.contains("func (self Suites) str() string {\n\treturn self.String()\n}"));
+    assert!(actual.contains("func SuitesFromStr(s string) rt.Option[Suites] {"));
+    assert!(actual.contains("\tcase \"Spades\":\n\t\treturn rt.Some[Suites]{F0: Spades}\n"));
+    assert!(actual.contains("\tdefault:\n\t\treturn rt.None[Suites]{}\n"));
+    assert!(actual.contains("\treturn SuitesFromStr(s)\n"));
+    assert!(actual.contains(
+        "func (self DayOfWeek) is_weekend() bool {\n\tswitch self {\n\tcase Saturday, Sunday:\n"
+    ));
+}
+
+#[test]
+fn go_interface_impl() {
+    let src = r"
+interface equal:
+    __equal__(any) -> bool
+end
+
+interface anything:
+end
+
+enum Option:
+    Some(Int)
+    None
+end
+
+impl equal for Option:
+    func __equal__(a: any) -> bool:
+        false
+    end
+end
+
+func same(e: equal, x: any) -> bool:
+    e.__equal__(x)
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("type equal interface {\n\t__equal__(any) bool\n}"));
+    assert!(actual.contains("type anything interface{}"));
+    // Enums must still satisfy the interface once they are boxed in their own interface
+    assert!(actual.contains("type Option interface {\n\tisOption()\n\t__equal__(a any) bool\n}"));
+    assert!(actual.contains("func (self Some) __equal__(a any) bool {"));
+    assert!(actual.contains("return e.__equal__(x)"));
+}
+
+#[test]
+fn go_magic_methods() {
+    let src = r"
+struct Money:
+    cents: Int
+end
+
+impl Money:
+    func __add__(other: Money) -> Money:
+        Money(cents = self.cents + other.cents)
+    end
+    func __equal__(other: Money) -> Bool:
+        self.cents == other.cents
+    end
+    func __bool__() -> Bool:
+        self.cents != 0
+    end
+    func __index__(i: Int) -> Int:
+        self.cents / i
+    end
+end
+
+func total(a: Money, b: Money) -> Money:
+    let sum = a + b
+    if sum != a and not b then:
+        let cents = sum[100] * (2 - 1)
+    end
+    sum
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("return Money{cents: self.cents + other.cents}"));
+    assert!(actual.contains("sum := a.__add__(b)"));
+    assert!(actual.contains("if !sum.__equal__(a) && !b.__bool__() {"));
+    assert!(actual.contains("cents := sum.__index__(100) * (2 - 1)"));
+}
+
+#[test]
+fn go_truthy_conditions() {
+    let src = r"
+enum Option:
+    Some(Int)
+    None
+end
+
+func pick(n: Int, s: String, o: Option, b: Bool) -> Int:
+    if n and not s then:
+        let x = 1
+    end
+    if not o or b then:
+        let y = 2
+    end
+    if b and n - 1 then 1 else 0
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("if n != 0 && !(len(s) != 0) {"));
+    assert!(actual.contains("if !(o != (None{})) || b {"));
+    assert!(actual.contains("if b && (n - 1) != 0 {"));
+}
+
+#[test]
+fn go_generic_funcs() {
+    let src = r"
+interface Shape:
+    area() -> Int
+end
+
+func largest[S: Shape](a: S, b: S) -> Int:
+    a.area() + b.area()
+end
+
+func get[K, V](m: map[K, V], key: K) -> V:
+    m[key]
+end
+
+func first[T](items: list[T]) -> T:
+    items[0]
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("func largest[S Shape](a S, b S) int {"));
+    assert!(actual.contains("func get[K comparable, V any](m map[K]V, key K) V {"));
+    assert!(actual.contains("func first[T any](items []T) T {"));
+}
+
+#[test]
+fn go_generic_enum_marker() {
+    let src = "\
+enum Tree[T]:
+    Leaf(T)
+    Node(Tree[T], Tree[T])
+end
+
+enum Either[L, R]:
+    Left(L)
+    Right(R)
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("type Tree[T any] interface {\n\tisTree(T)\n}"));
+    assert!(actual.contains("func (Leaf[T]) isTree(T) {}"));
+    assert!(actual.contains("func (Node[T]) isTree(T) {}"));
+    assert!(actual.contains("type Either[L any, R any] interface {\n\tisEither(L, R)\n}"));
+    assert!(actual.contains("func (Right[L, R]) isEither(L, R) {}"));
+}
+
+#[test]
+fn go_generic_types() {
+    let src = r"
+struct Box[T]:
+    value: T
+end
+
+impl Box[T]:
+    func get() -> T:
+        self.value
+    end
+end
+
+enum Option[T]:
+    Some(T)
+    None
+end
+
+func unwrap_or[T](opt: Option[T], default_val: T) -> T:
+    match opt with:
+        Some(value) -> value
+        None -> default_val
+    end
+end
+
+func my_func() -> Option[Box[Int]]:
+    let empty: Option[Int] = None
+    if empty then:
+        1
+    end
+    Some(Box(unwrap_or(empty, 2)))
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("type Box[T any] struct {"));
+    assert!(actual.contains("func (self Box[T]) get() T {"));
+    assert!(actual.contains("type Option[T any] interface {"));
+    assert!(actual.contains("type Some[T any] struct {"));
+    assert!(actual.contains("type Option[T any] interface {\n\tisOption(T)\n"));
+    assert!(actual.contains("func (None[T]) isOption(T) {}"));
+    assert!(actual.contains("case Some[T]:"));
+    assert!(actual.contains("var empty Option[int] = None[int]{}"));
+    assert!(actual.contains("if empty != (None[int]{}) {"));
+    assert!(actual.contains("return Some[Box[int]]{F0: Box[int]{value: unwrap_or(empty, 2)}}"));
+}
+
+#[test]
+fn go_prelude() {
+    let src = r"
+func find(items: list[Int], item: Int) -> Option[Int]:
+    if items then:
+        Some(item)
+    else:
+        None
+    end
+end
+
+func my_func(opt: Option[Int], res: Result[Int]) -> Int:
+    let first: Option[Int] = None
+    match res.ok() with:
+        Some(value) -> value
+        None -> opt.unwrap_or(0)
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.starts_with("import rt \"github.com/nu11ptr/interop/rt\"\n\n"));
+    assert!(actual.contains("func find(items []int, item int) rt.Option[int] {"));
+    assert!(actual.contains("rt.Some[int]{F0: item}"));
+    assert!(actual.contains("var first rt.Option[int] = rt.None[int]{}"));
+    assert!(actual.contains("__match0 := res.Ok()\n"));
+    assert!(actual.contains("\tcase rt.Some[int]:\n\t\tvalue := __v1.F0\n"));
+    assert!(actual.contains("return opt.UnwrapOr(0)"));
+
+    let actual = gen_go("func my_func() -> 1\n");
+    assert!(!actual.contains("import"));
+}
+
+#[test]
+fn go_prelude_map() {
+    let src = r#"
+func describe(n: Int) -> str:
+    n.str()
+end
+
+func my_func(opt: Option[Int], res: Result[Int]) -> str:
+    let names: Option[str] = opt.map(describe)
+    res.map(describe).unwrap_or(names.unwrap_or("none"))
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains("var names rt.Option[string] = rt.MapOption(opt, describe)\n"));
+    assert!(
+        actual.contains("return rt.MapResult(res, describe).UnwrapOr(names.UnwrapOr(\"none\"))\n")
+    );
+}
+
+#[test]
+fn go_try() {
+    let src = r#"
+func parse(s: str) -> Result[Int]:
+    Ok(1)
+end
+
+func log(err: error) -> error:
+    err
+end
+
+struct Lookup:
+    found: Bool
+end
+
+impl Lookup:
+    func __try__() -> Result[str]:
+        Ok("found")
+    end
+end
+
+func add(a: str, b: str, l: Lookup) -> Result[Int]:
+    let x = try parse(a) catch as e:
+        log(e)
+    end
+
+    let sum =
+        try:
+            y <- parse(b)
+            z <- parse(a)
+            x + y + z
+        catch:
+            Err(err)
+        end
+
+    try l catch:
+        err
+    end
+
+    try:
+        w <- parse(b)
+        Ok(w + sum)
+    catch:
+        err
+    end
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "\tx, e := parse(a).Get()\n\tif e != nil {\n\t\treturn rt.Err[int]{F0: log(e)}\n\t}\n"
+    ));
+    assert!(actual.contains("\ty, err := parse(b).Get()\n"));
+    assert!(actual.contains("\tz, err := parse(a).Get()\n"));
+    assert!(actual.contains("\t\treturn rt.Err[int]{F0: err}\n\t}\n\tsum := x + y + z\n"));
+    assert!(actual.contains("\tif _, err := l.__try__().Get(); err != nil {\n"));
+    assert!(actual.contains("\tw, err := parse(b).Get()\n"));
+    assert!(actual.contains("\treturn rt.Ok[int]{F0: w + sum}\n}"));
+}
+
+#[test]
+fn go_with() {
+    let src = r"
+struct File:
+    name: str
+end
+
+impl context for File:
+    func Enter() -> 1
+    func Exit() -> 1
+end
+
+func parse(s: str) -> Result[Int]:
+    Ok(1)
+end
+
+func enter(c: context) -> c.Enter()
+
+func load(f: File, g: File) -> Result[Int]:
+    with f as f:
+        print(f.name)
+    end
+
+    let size =
+        with g:
+            try parse(f.name) catch:
+                err
+            end
+            1
+        end
+
+    with f as f:
+        Ok(size)
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("func enter(c rt.Context) {"));
+    assert!(
+        actual.contains("\t{\n\t\tf := f\n\t\tf.Enter()\n\t\tprint(f.name)\n\t\tf.Exit()\n\t}\n")
+    );
+    assert!(actual.contains("\tvar size int\n\t{\n\t\t__with0 := g\n\t\t__with0.Enter()\n"));
+    assert!(actual.contains(
+        "\t\t\t__ret1 := rt.Err[int]{F0: err}\n\t\t\t__with0.Exit()\n\t\t\treturn __ret1\n"
+    ));
+    assert!(actual.contains("\t\tsize = 1\n\t\t__with0.Exit()\n\t}\n"));
+    assert!(actual.contains("\t\tdefer f.Exit()\n\t\treturn rt.Ok[int]{F0: size}\n\t}\n}"));
+}
+
+#[test]
+fn go_comprehensions() {
+    let src = r#"
+func my_func(xs: list[Int], m: map[str, Int], it: iter[Int]) -> set[Int]:
+    let doubled = [x * 2 <- for x in xs if x]
+    let values = {k: m[k] <- for k in m if k != "a"}
+    let lazy = (x + m["a"] <- for x in doubled)
+    {|x <- for x in it|}
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual
+        .contains("func my_func(xs []int, m map[string]int, it rt.Iter[int]) map[int]struct{} {"));
+    assert!(actual.contains(
+        "\tdoubled := func() []int {\n\t\t__result0 := []int{}\n\t\tfor _, x := range xs {\n\t\t\tif x != 0 {\n\t\t\t\t__result0 = append(__result0, x * 2)\n\t\t\t}\n\t\t}\n\t\treturn __result0\n\t}()\n"
+    ));
+    assert!(actual
+        .contains("\t\tfor k := range m {\n\t\t\tif k != \"a\" {\n\t\t\t\t__result1[k] = m[k]\n"));
+    assert!(
+        actual.contains("\tlazy := rt.Iter[int](&__iter2{__source: rt.Elements(doubled), m: m})\n")
+    );
+    assert!(actual.contains(
+        "}\n\ntype __iter2 struct {\n\t__source rt.Iter[int]\n\tm map[string]int\n}\n\nfunc (__self *__iter2) Next() (int, bool) {\n\tm := __self.m\n\tfor x, __ok3 := __self.__source.Next(); __ok3; x, __ok3 = __self.__source.Next() {\n\t\treturn x + m[\"a\"], true\n\t}\n\treturn rt.Done[int]()\n}\n\nfunc (__self *__iter2) Close() {\n\trt.Close(__self.__source)\n}"
+    ));
+    assert!(actual.contains(
+        "\treturn func() map[int]struct{} {\n\t\t__result4 := map[int]struct{}{}\n\t\t__source5 := it\n\t\tdefer rt.Close(__source5)\n"
+    ));
+    assert!(actual.contains("\t\t\t__result4[x] = struct{}{}\n"));
+}
+
+#[test]
+fn go_comprehensions_iter_struct_generic() {
+    let src = r"
+struct Box[T]:
+    items: list[T]
+    step: Int
+end
+
+impl Box[T]:
+    func items_iter() -> iter[T]:
+        (x <- for x in self.items if self.step)
+    end
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "\treturn rt.Iter[T](&__iter0[T]{__source: rt.Elements(self.items), self: self})\n"
+    ));
+    assert!(actual.contains(
+        "type __iter0[T any] struct {\n\t__source rt.Iter[T]\n\tself Box[T]\n}\n\nfunc (__self *__iter0[T]) Next() (T, bool) {\n\tself := __self.self\n"
+    ));
+}
+
+#[test]
+fn go_comprehensions_without_annotations() {
+    let src = r"
+func my_func(xs: list[Int]) -> iter[Int]:
+    (x + 1 <- for x in xs)
+end
+";
+    let decls = crate::compile(src).expect("file node");
+    let mut codegen = crate::codegen_go::GoCodegen::new();
+    let actual = codegen
+        .gen_code(&crate::prelude(&decls), &decls)
+        .to_string();
+    assert!(actual.contains("\treturn rt.Iter[any](&__iter0{__source: rt.Elements(xs)})\n"));
+}
+
+#[test]
+fn go_coroutine() {
+    let src = r"
+func count(n: Int) -> coroutine[Int, ()]:
+    yield n
+    yield n + 1
+end
+
+func running_total() -> coroutine[Int, Int]:
+    let x = yield 0
+    yield x + (yield x)
+end
+
+func tens() -> list[Int]:
+    [x * 10 <- for x in count(1)]
+end
+
+func lazy_tens() -> iter[Int]:
+    (x * 10 <- for x in count(1))
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "func count(n int) *rt.Coroutine[int, struct{}] {\n\treturn rt.NewCoroutine(func(yield func(int) struct{}) {\n\t\tyield(n)\n\t\tyield(n + 1)\n\t})\n}\n"
+    ));
+    assert!(actual.contains("\t\tx := yield(0)\n\t\tyield(x + (yield(x)))\n"));
+    assert!(actual.contains(
+        "\t\t__source1 := count(1)\n\t\tdefer rt.Close(__source1)\n\t\tfor x, __ok2 := __source1.Next(); __ok2; x, __ok2 = __source1.Next() {\n"
+    ));
+    assert!(actual.contains("\treturn rt.Iter[int](&__iter3{__source: count(1)})\n"));
+    assert!(actual.contains("func (__self *__iter3) Close() {\n\trt.Close(__self.__source)\n}"));
+}
+
+#[test]
+fn go_string_char_escapes() {
+    let src = r#"
+func my_func(c: char):
+    print("\{x\}\u0000e9\0", '\o101', "{c}\t")
+    match c with:
+        '\x41' -> 1
+        _ -> 2
+    end
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains("func my_func(c rune) {\n"));
+    assert!(actual.contains(r#"print("{x}é\x00", 'A', fmt.Sprintf("%c\t", c))"#));
+    assert!(actual.contains("\tcase 'A':\n"));
+}
+
+#[test]
+fn go_raw_strings() {
+    let src = r#"
+func my_func():
+    print(``"\d+ "{x}""``, ``"a`b\c"``)
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains(r#"print(`\d+ "{x}"`, "a`b\\c")"#));
+}
+
+#[test]
+fn go_interpolated_string() {
+    let src = r#"
+func greet(name: str, n: Int, xs: list[Int]) -> str:
+    "hello {name}: {n}% of {xs} \{name\}"
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.starts_with("import \"fmt\"\n\n"));
+    assert!(actual.contains("\treturn fmt.Sprintf(\"hello %s: %d%% of %v {name}\", name, n, xs)\n"));
+}
+
+#[test]
+fn go_async() {
+    let src = r"
+async func fetch(n: Int) -> Int:
+    n * 2
+end
+
+async func total() -> Int:
+    let a = fetch(1)
+    await a + await fetch(2)
+end
+
+async func log(msg: str):
+    print(msg)
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains(
+        "func fetch(n int) rt.Future[int] {\n\treturn rt.Async(func() int {\n\t\treturn n * 2\n\t})\n}\n"
+    ));
+    assert!(actual.contains("\t\ta := fetch(1)\n\t\treturn a.Await() + fetch(2).Await()\n"));
+    assert!(actual.contains(
+        "func log(msg string) rt.Future[struct{}] {\n\treturn rt.Async(func() struct{} {\n\t\tprint(msg)\n\t\treturn struct{}{}\n\t})\n}"
+    ));
+}
+
+#[test]
+fn go_call_args() {
+    let src = r#"
+struct Point:
+    x: Int
+    y: Int
+end
+
+impl Point:
+    func shift(dx: Int = 0, dy: Int = 0) -> Point:
+        Point(self.x + dx, self.y + dy)
+    end
+end
+
+func greet(name: str, greeting: str = "hello", origin: Point = Point(0, 0)) -> str:
+    greeting + name
+end
+
+func my_func(p: Point) -> str:
+    p.shift(dy=2)
+    greet(greeting="hi", name="bob")
+    greet("amy")
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains("\tp.shift(0, 2)\n"));
+    assert!(actual.contains("\tgreet(\"bob\", \"hi\", Point{x: 0, y: 0})\n"));
+    assert!(actual.contains("\treturn greet(\"amy\", \"hello\", Point{x: 0, y: 0})\n"));
+}
+
+#[test]
+fn go_local_func_call_args() {
+    let src = r#"
+func my_func() -> Int:
+    func inner(a: Int, b: Int = 2) -> Int:
+        a + b
+    end
+    inner(b=3, a=1)
+    inner(1)
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.contains("\tinner(1, 3)\n"));
+    assert!(actual.contains("\treturn inner(1, 2)\n"));
+}
+
+#[test]
+fn go_top_level_bindings() {
+    let src = r#"
+const max_size: Int = 10 * 2
+const name = "bob"
+let greeting = greet()
+let var count = max_size + 1
+
+func greet() -> str:
+    name
+end
+"#;
+    let actual = gen_go(src);
+    assert!(actual.starts_with(
+        "const max_size int = 10 * 2\n\nconst name = \"bob\"\n\nvar greeting = greet()\n\nvar count = max_size + 1\n\n"
+    ));
+}
+
+#[test]
+fn go_type_decls() {
+    let src = r"
+type Meters Int
+type Distance = Meters
+type Names = list[str]
+
+func walk(d: Distance) -> Meters:
+    d + Meters(1)
+end
+";
+    let actual = gen_go(src);
+    assert!(actual.contains("type Meters int\n\ntype Distance = Meters\n\ntype Names = []string\n"));
+    assert!(actual.contains("func walk(d Distance) Meters {\n\treturn d + Meters(1)\n}"));
+}
+
+#[test]
+fn go_type_alias_float() {
+    let src = r"
+type Meters = float
+
+func double(m: Meters) -> Meters:
+    m + m
+end
+";
+    assert_eq!(check(src), Vec::<String>::new());
+    let actual = gen_go(src);
+    assert!(actual.contains("type Meters = float64\n"));
+    assert!(actual.contains("func double(m Meters) Meters {\n"));
+}
+
+// *** Checks ***
+
+fn check(src: &str) -> Vec<String> {
+    let decls = crate::compile(src).expect("file node");
+    crate::check::check(&crate::prelude(&decls), &decls)
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect()
+}
+
+#[test]
+fn check_match_bool_exhaustive() {
+    let src = r"
+func my_func(abc: Bool) -> Int:
+    match abc with:
+        true  -> 1
+        false -> 2
+    end
+end
+";
+    assert_eq!(check(src), Vec::<String>::new());
+}
+
+#[test]
+fn check_match_bool_missing() {
+    let src = r"
+func my_func(abc: Bool) -> Int:
+    match abc with:
+        true -> 1
+    end
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `my_func`: non-exhaustive match: `false` not covered"]
+    );
+}
+
+#[test]
+fn check_match_literals_missing() {
+    let src = r"
+func my_func(abc: Int) -> Int:
+    match abc with:
+        1, 2 -> 1
+    end
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `my_func`: non-exhaustive match: `_` not covered"]
+    );
+}
+
+#[test]
+fn check_match_unreachable() {
+    let src = r"
+func my_func(abc: Int) -> Int:
+    match abc with:
+        1, 2    -> 1
+        3, 2    -> 2
+        x       -> 3
+        4       -> 4
+    end
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "warning: in func `my_func`: unreachable match pattern `2`",
+            "warning: in func `my_func`: unreachable match pattern `4`",
+        ]
+    );
+}
+
+#[test]
+fn check_match_nested_missing() {
+    let src = r"
+func my_func(abc: Any) -> Int:
+    match abc with:
+        Pair(true, _) -> 1
+        Pair(_, false) -> 2
+        _: Int -> 3
+    end
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `my_func`: non-exhaustive match: `_` not covered"]
+    );
+}
+
+#[test]
+fn check_match_positions() {
+    let src = r"
+func my_func(x: Int, s: Str) -> Int:
+    let y = (match x with:
+        1 -> 10
+        _ -> s
+    end)
+    1 + (match x with:
+        _ -> 1
+    end)
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: the type of the `match` value of `y` can't be inferred, so the `let` needs one",
+            "error: in func `my_func`: `match` can only be used as a statement, as the value of a `let` or as the value of a match arm",
+        ]
+    );
+}
+
+#[test]
+fn check_match_enum_missing() {
+    let src = r"
+        match day with:
+            Monday, Tuesday, Wednesday, Thursday, Friday -> false
+            Sunday                                       -> true
+        end";
+    let ast::Expr::Match(match_) = expr_parser(src).expect("match node") else {
+        panic!("expected match node");
+    };
+
+    let mut exhaustiveness = crate::exhaustive::Exhaustiveness::new();
+    exhaustiveness.add_enum(
+        "DayOfWeek",
+        &[
+            ("Sunday", 0),
+            ("Monday", 0),
+            ("Tuesday", 0),
+            ("Wednesday", 0),
+            ("Thursday", 0),
+            ("Friday", 0),
+            ("Saturday", 0),
+        ],
+    );
+    let report = exhaustiveness.check(&match_.arms);
+    assert_eq!(report.missing, vec!["Saturday"]);
+    assert!(report.unreachable.is_empty());
+}
+
+#[test]
+fn check_match_enum_nested_missing() {
+    let src = r"
+        match opt with:
+            Some(true) -> 1
+            None       -> 2
+            Some(_)    -> 3
+            None       -> 4
+        end";
+    let ast::Expr::Match(match_) = expr_parser(src).expect("match node") else {
+        panic!("expected match node");
+    };
+
+    let mut exhaustiveness = crate::exhaustive::Exhaustiveness::new();
+    exhaustiveness.add_enum("Option", &[("Some", 1), ("None", 0)]);
+    let report = exhaustiveness.check(&match_.arms);
+    assert!(report.missing.is_empty());
+    assert_eq!(report.unreachable, vec!["None"]);
+
+    let report = exhaustiveness.check(&match_.arms[..2]);
+    assert_eq!(report.missing, vec!["Some(false)"]);
+}
+
+#[test]
+fn check_struct_fields() {
+    let src = r"
+struct Point:
+    x: Int
+    y: Int
+end
+
+func my_func(p: Point) -> Int:
+    let q = Point(y=1, x=2)
+    p.z
+    q.y
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `my_func`: struct `Point` has no field or method `z`"]
+    );
+}
+
+#[test]
+fn check_struct_field_chain() {
+    let src = r"
+struct Point:
+    x: Int
+    y: Int
+end
+
+struct Line:
+    start: Point
+end
+
+impl Line:
+    func start_x() -> Int:
+        self.start.x
+    end
+
+    func finish_x() -> Int:
+        self.finish.x
+    end
+end
+
+func my_func(l: Line) -> l.start.w
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `finish_x`: struct `Line` has no field or method `finish`",
+            "error: in func `my_func`: struct `Point` has no field or method `w`",
+        ]
+    );
+}
+
+#[test]
+fn check_struct_constructor() {
+    let src = r"
+struct Point:
+    x: Int
+    y: Int
+end
+
+func my_func():
+    Point(1, 2, 3)
+    Point(1, x=2)
+    Point(z=1)
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: too many arguments to constructor of struct `Point`",
+            "error: in func `my_func`: field `x` given more than once to constructor of struct `Point`",
+            "error: in func `my_func`: missing field `y` in constructor of struct `Point`",
+            "error: in func `my_func`: struct `Point` has no field `z`",
+            "error: in func `my_func`: missing field `x` in constructor of struct `Point`",
+            "error: in func `my_func`: missing field `y` in constructor of struct `Point`",
+        ]
+    );
+}
+
+#[test]
+fn check_call_args() {
+    let src = r"
+func add(a: Int, b: Int = 1) -> Int:
+    a + b
+end
+
+func my_func() -> Int:
+    add(1, 2, 3)
+    add(1, a=2)
+    add(b=2)
+    add(1, c=2)
+    add(b=1, a=2)
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: too many arguments in call to func `add`",
+            "error: in func `my_func`: argument `a` given more than once in call to func `add`",
+            "error: in func `my_func`: missing argument `a` in call to func `add`",
+            "error: in func `my_func`: func `add` has no argument `c`",
+        ]
+    );
+}
+
+#[test]
+fn check_call_args_defaults() {
+    let src = r"
+func f(a: Int = 1, b: Int = a) -> Int:
+    a + b
+end
+
+func my_func() -> Int:
+    func inner(a: Int, b: Int = 2) -> Int:
+        a + b
+    end
+    inner(b=1)
+    f()
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `f`: a default value can't refer to argument `a`",
+            "error: in func `my_func`: missing argument `a` in call to func `inner`",
+        ]
+    );
+}
+
+#[test]
+fn check_top_level_bindings() {
+    let src = r"
+const size = 10
+const bad = twice(size)
+let a = b + 1
+let b = twice(2)
+let c = shadow(1)
+let size = 2
+
+func twice(x: Int) -> Int:
+    a * x
+end
+
+func shadow(c: Int) -> Int:
+    c + size
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: `size` is declared more than once",
+            "error: the value of const `bad` must be known at compile time",
+            "error: initialization cycle: `a` refers to `b` refers to `twice` refers to `a`",
+        ]
+    );
+}
+
+#[test]
+fn check_top_level_bindings_methods() {
+    let src = r"
+struct S:
+    x: Int
+end
+
+impl S:
+    func get() -> Int:
+        a + self.x
+    end
+
+    func twice() -> Int:
+        self.get() * 2
+    end
+end
+
+let a = S(1).twice()
+let b = S(2).x
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: initialization cycle: `a` refers to `S.twice` refers to `S.get` refers to `a`"
+        ]
+    );
+}
+
+#[test]
+fn check_type_decls() {
+    let src = r"
+type Meters Int
+type Feet Int
+type Distance = Meters
+type Loop = list[Loop]
+type Seconds Int
+type Seconds = Int
+
+func walk(d: Distance) -> Meters:
+    d + 1
+end
+
+func my_func(m: Meters, f: Feet, names: list[Distance]) -> Meters:
+    let a = m + f
+    let b = names[0] + m
+    walk(f)
+    Meters(1, 2)
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: type `Seconds` is declared more than once",
+            "error: type alias `Loop` refers to itself",
+            "error: in func `my_func`: mismatched types `Meters` and `Feet` in `+`",
+            "error: in func `my_func`: expected a `Distance` argument, but a `Feet` was given",
+            "error: in func `my_func`: newtype `Meters` takes 1 argument, but 2 were given",
+        ]
+    );
+}
+
+#[test]
+fn check_type_decls_in_interfaces() {
+    let src = r#"
+type Name = str
+type Id str
+
+interface Named:
+    name() -> str
+end
+
+struct Person:
+    id: Id
+end
+
+struct Robot:
+    id: Id
+end
+
+impl Named for Person:
+    func name() -> Name:
+        "bob"
+    end
+end
+
+impl Robot:
+    func name() -> Id:
+        self.id
+    end
+end
+
+func greet[N: Named](n: N) -> str:
+    n.name()
+end
+
+func my_func(p: Person, r: Robot) -> str:
+    greet(p)
+    greet(r)
+end
+"#;
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: `Robot` doesn't implement interface `Named` (method `name` is `() -> Id`, but `() -> str` is expected), which is required by type parameter `N` of func `greet`",
+        ]
+    );
+}
+
+#[test]
+fn check_struct_decls() {
+    let src = r"
+struct Point:
+    x: Int
+    x: Int
+end
+
+impl Point:
+    func x() -> 1
+    func new(y: Int):
+        let self.y = y
+    end
+end
+
+impl Line:
+    func len() -> 1
+end
+
+func my_func() -> self.x
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: impl for unknown type `Line`",
+            "error: field `x` is declared more than once in struct `Point`",
+            "error: method `x` has the same name as a field of struct `Point`",
+            "error: in func `new`: struct `Point` has no field `y`",
+            "error: in func `my_func`: `self` used outside of an impl",
+        ]
+    );
+}
+
+#[test]
+fn check_match_struct_exhaustive() {
+    let src = r"
+struct Point:
+    x: Int
+    y: Bool
+end
+
+func my_func(p: Point) -> Int:
+    match p with:
+        Point(x, true) -> x
+        Point(_, false) -> 2
+    end
+end
+";
+    assert_eq!(check(src), Vec::<String>::new());
+}
+
+#[test]
+fn check_enum_match() {
+    let src = r"
+enum Shape:
+    Circle(Int)
+    Rect(Int, Int)
+    Empty
+end
+
+func area(s: Shape) -> Int:
+    match s with:
+        Circle(r)  -> r
+        Rect(w, _) -> w
+    end
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `area`: non-exhaustive match: `Empty` not covered"]
+    );
+}
+
+#[test]
+fn check_enum_variants() {
+    let src = r"
+enum Shape:
+    Circle(Int)
+    Empty
+end
+
+impl Shape:
+    func radius() -> Int:
+        match self with:
+            Circle(r, _) -> r
+            Empty        -> 1
+        end
+    end
+end
+
+func my_func():
+    Circle(1, 2)
+    Circle(r=1)
+    Circle
+    Empty.radius()
+    Empty.area
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `radius`: variant `Circle` has 1 field(s), but the pattern has 2",
+            "error: in func `my_func`: variant `Circle` takes 1 argument(s), but 2 were given",
+            "error: in func `my_func`: variant `Circle` doesn't take named arguments",
+            "error: in func `my_func`: variant `Circle` takes 1 argument(s), but 0 were given",
+            "error: in func `my_func`: enum `Shape` has no field or method `area`",
+        ]
+    );
+}
+
+#[test]
+fn check_enum_decls() {
+    let src = r"
+struct Empty:
+    a: Int
+end
+
+enum Shape:
+    Circle(Int)
+    Empty
+end
+
+enum Circle:
+    Shape
+end
+
+impl Shape:
+    func new() -> 1
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: variant `Circle` is declared more than once",
+            "error: variant `Empty` is declared more than once",
+            "error: variant `Shape` is declared more than once",
+            "error: enum `Shape` can't have a `new` function",
+        ]
+    );
+}
+
+#[test]
+fn check_enum_values() {
+    let src = r"
+enum Suites:
+    Hearts = 1
+    Spades
+    Diamonds = 2
+    Clubs = 1
+end
+
+enum Option:
+    Some(Int) = 1
+    None
+end
+
+impl Suites:
+    func str() -> 1
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: variant `Diamonds` of enum `Suites` has the same value (2) as `Spades`",
+            "error: variant `Clubs` of enum `Suites` has the same value (1) as `Hearts`",
+            "error: variant `Some` of enum `Option` can't have a value, since the enum has payloads",
+            "error: method `str` is generated for enum `Suites`, so it can't be declared",
+        ]
+    );
+}
+
+#[test]
+fn check_enum_value_ranges() {
+    let src = r"
+enum Suites:
+    Hearts = 0..3
+    Spades
+    Diamonds
+    Clubs
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: variant `Hearts` of enum `Suites` can't have a range of values (0..3): give it a \
+            single value, and the variants after it count up from there",
+        ]
+    );
+}
+
+#[test]
+fn check_enum_str() {
+    let src = r"
+enum DayOfWeek:
+    Sunday
+    Monday
+end
+
+func name(d: DayOfWeek) -> d.str().len
+func other(d: DayOfWeek) -> d.name()
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `other`: enum `DayOfWeek` has no field or method `name`"]
+    );
+}
+
+#[test]
+fn check_enum_from_str() {
+    let src = r"
+enum DayOfWeek:
+    Sunday
+    Monday
+end
+
+struct Point:
+    x: Int
+end
+
+func parse(s: str) -> DayOfWeek:
+    DayOfWeek.from_str(s).unwrap_or(Sunday)
+end
+
+func other() -> DayOfWeek.parse()
+func point() -> Point.from_str()
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `other`: enum `DayOfWeek` has no static member `parse`",
+            "error: in func `point`: struct `Point` has no static member `from_str`",
+        ]
+    );
+}
+
+#[test]
+fn check_interface_impl() {
+    let src = r"
+interface shape:
+    area() -> Int
+    scale(Int) -> shape
+    name() -> str
+end
+
+struct Square:
+    side: Int
+end
+
+impl shape for Square:
+    func area() -> Int:
+        self.side
+    end
+
+    func scale(by: str) -> shape:
+        self
+    end
+
+    func extra() -> 1
+end
+
+impl drawable for Square:
+    func draw() -> 1
+end
+
+impl shape:
+    func area() -> 1
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: interface `shape` can't have an impl, only types can",
+            "error: method `scale` of `Square` doesn't match interface `shape`: expected `(Int) -> shape`, found `(str) -> shape`",
+            "error: method `name` of interface `shape` is not implemented for `Square`",
+            "error: method `extra` is not part of interface `shape`",
+            "error: unknown interface `drawable`",
+        ]
+    );
+}
+
+#[test]
+fn check_interface_methods() {
+    let src = r"
+interface shape:
+    area() -> Int
+    area() -> Int
+end
+
+func my_func(s: shape) -> Int:
+    s.area()
+    s.perimeter()
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: method `area` is declared more than once in interface `shape`",
+            "error: in func `my_func`: interface `shape` has no field or method `perimeter`",
+        ]
+    );
+}
+
+#[test]
+fn check_magic_methods() {
+    let src = r"
+struct Meters:
+    value: Int
+end
+
+impl Meters:
+    func __add__(other: Meters) -> Meters:
+        Meters(value = self.value + other.value)
+    end
+end
+
+func my_func(a: Meters, b: Meters) -> Meters:
+    let c = a + b
+    let d = c * b
+    if c then:
+        c[0]
+    end
+    c == d
+    c
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: struct `Meters` doesn't support `*` (it has no `__mul__` method)",
+            "error: in func `my_func`: struct `Meters` can't be used as a condition (it has no `__bool__` method)",
+            "error: in func `my_func`: struct `Meters` can't be indexed (it has no `__index__` method)",
+            "error: in func `my_func`: struct `Meters` doesn't support `==` (it has no `__equal__` method)",
+        ]
+    );
+}
+
+#[test]
+fn check_truthy_conditions() {
+    let src = r"
+enum Maybe:
+    Just(Int)
+    None
+end
+
+enum Color:
+    Red
+    Blue
+end
+
+impl Color:
+    func __bool__() -> Bool:
+        false
+    end
+end
+
+enum Shape:
+    Circle(Int)
+    Square(Int)
+end
+
+func my_func(m: Maybe, c: Color, s: Shape) -> Int:
+    if m and c then:
+        1
+    end
+    if not s then 1 else 0
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `my_func`: enum `Shape` can't be used as a condition (it has no `__bool__` method)"]
+    );
+}
+
+#[test]
+fn check_generic_funcs() {
+    let src = r#"
+interface Shape:
+    area() -> Int
+end
+
+struct Square:
+    side: Int
+end
+
+func area_sum[S: Shape](a: S, b: S) -> Int:
+    a.area() + b.perimeter()
+end
+
+func same[T](a: T, b: T) -> T:
+    a
+end
+
+func my_func(sq: Square) -> Int:
+    let n: Int = same(1, 2)
+    same(1, "a")
+    area_sum(sq, sq)
+end
+"#;
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `area_sum`: type parameter `S` has no field or method `perimeter`",
+            "error: in func `my_func`: type parameter `T` of func `same` can't be both `int` and `str`",
+            "error: in func `my_func`: `Square` doesn't implement interface `Shape` (missing method `area`), which is required by type parameter `S` of func `area_sum`",
+        ]
+    );
+}
+
+#[test]
+fn check_type_params() {
+    let src = r"
+struct Square:
+    side: Int
+end
+
+func pair[A, A: Square](x: A) -> A:
+    x
+end
+
+func make[T, Square]() -> Int:
+    1
+end
+
+func bounded[T: Drawable](x: T) -> T:
+    x
+end
+
+impl Square:
+    func cast[T](x: T) -> T:
+        x
+    end
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `pair`: type parameter `A` is declared more than once",
+            "error: in func `pair`: bound `Square` of type parameter `A` is a struct, not an interface",
+            "error: in func `make`: type parameter `Square` has the same name as a type",
+            "error: in func `make`: type parameter `T` isn't used by any argument, so it can't be inferred",
+            "error: in func `make`: type parameter `Square` isn't used by any argument, so it can't be inferred",
+            "error: in func `bounded`: unknown interface `Drawable`",
+            "error: method `cast` of struct `Square` can't have type parameters",
+        ]
+    );
+}
+
+#[test]
+fn check_generic_types() {
+    let src = r#"
+interface Shape:
+    area() -> Int
+end
+
+struct Box[T: Shape]:
+    value: T
+end
+
+enum Option[T]:
+    Some(T)
+    None
+end
+
+enum Color[T]:
+    Red
+    Green
+end
+
+struct Square:
+    side: Int
+end
+
+impl Square[T]:
+    func area() -> Int:
+        self.side
+    end
+end
+
+impl Option[U]:
+    func is_some() -> Bool:
+        true
+    end
+end
+
+func my_func(a: Option, b: Option[Int, Str], c: Square[Int], d: Box[Int]) -> Int:
+    let x = None
+    let y: Option[Str] = Some(1)
+    let z = Some("a")
+    z.is_some()
+    1
+end
+"#;
+    assert_eq!(
+        check(src),
+        vec![
+            "error: enum `Color` can't have type parameters, since no variant has a payload",
+            "error: struct `Square` has no type parameters, so its impl can't have any",
+            "error: impl for enum `Option` must have its type parameters `[T]`",
+            "error: in func `my_func`: enum `Option` needs 1 type argument(s)",
+            "error: in func `my_func`: enum `Option` takes 1 type argument(s), but 2 were given",
+            "error: in func `my_func`: struct `Square` doesn't take type arguments",
+            "error: in func `my_func`: `Int` doesn't implement interface `Shape` (missing method `area`), which is required by type parameter `T` of struct `Box`",
+            "error: in func `my_func`: type parameter `T` of enum `Option` can't be inferred here",
+            "error: in func `my_func`: type parameter `T` of enum `Option` can't be both `Str` and `int`",
+        ]
+    );
+}
+
+#[test]
+fn check_prelude() {
+    let prelude = crate::prelude(&[]);
+    let diagnostics: Vec<_> = crate::check::check(&[], &prelude)
+        .iter()
+        .map(|diagnostic| diagnostic.to_string())
+        .collect();
+    assert_eq!(diagnostics, Vec::<String>::new());
+
+    let src = r"
+func parse(e: error) -> Result[Int]:
+    let failed = Err(e)
+    Ok(1)
+end
+";
+    assert_eq!(
+        check(src),
+        vec!["error: in func `parse`: type parameter `T` of enum `Result` can't be inferred here"]
+    );
+}
+
+#[test]
+fn check_prelude_map() {
+    let src = r"
+func double(n: Int) -> Int:
+    n * 2
+end
+
+func shout(s: str) -> str:
+    s
+end
+
+func first[T](a: T) -> T:
+    a
+end
+
+func log(n: Int):
+    print(n)
+end
+
+func my_func(opt: Option[Int], res: Result[Int]) -> Int:
+    let a: Option[Int] = opt.map(double)
+    let b = res.map(double).unwrap_or(0)
+    opt.map(shout)
+    opt.map(first)
+    opt.map(log)
+    res.map(b)
+    opt.map(double, double)
+    b
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: func `shout` given to `map` takes a `str`, but the `Option` holds a `Int`",
+            "error: in func `my_func`: generic func `first` can't be given to `map`",
+            "error: in func `my_func`: func `log` given to `map` must declare its return type",
+            "error: in func `my_func`: `map` of `Result` takes the name of a func",
+            "error: in func `my_func`: `map` of `Option` takes the name of a func",
+        ]
+    );
+}
+
+#[test]
+fn check_try() {
+    let src = r#"
+struct Lookup:
+    found: Bool
+end
+
+impl Lookup:
+    func __try__() -> Result[str]:
+        Ok("found")
+    end
+end
+
+func my_func(l: Lookup, r: Result[Int]) -> Int:
+    z <- r
+    let a = try 1 catch:
+        0
+    end
+    let b = try:
+        let c = 1
+    catch:
+        0
+    end
+    print(try r catch:
+        0
+    end)
+    try:
+        d <- l
+        let e: str = d
+        if true then:
+            f <- r
+        end
+    catch:
+        0
+    end
+    0
+end
+"#;
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: `<-` can only be used directly in a `try` block",
+            "error: in func `my_func`: `int` can't be unwrapped (it isn't a `Result` and has no \
+            `__try__` method)",
+            "error: in func `my_func`: a `try` block used as a value must end with an expression \
+            or `<-`",
+            "error: in func `my_func`: `try` can only be used as a statement or as the value of a \
+            `let`",
+            "error: in func `my_func`: `<-` can only be used directly in a `try` block",
+        ]
+    );
+}
+
+#[test]
+fn check_with() {
+    let src = r"
+struct Lock:
+    held: Bool
+end
+
+impl Lock:
+    func Enter() -> 1
+end
+
+func unknown() -> 1
+
+func my_func(l: Lock) -> Int:
+    with l:
+        1
+    end
+    let a =
+        with 1 as b:
+            let c = b
+        end
+    let d =
+        with l:
+            unknown()
+        end
+    print(with l:
+        1
+    end)
+    0
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: `Lock` can't be used with `with`, as it doesn't implement \
+            interface `context` (missing method `Exit`)",
+            "error: in func `my_func`: a `with` block used as a value must end with an expression",
+            "error: in func `my_func`: `int` can't be used with `with`, as it doesn't implement \
+            interface `context` (missing method `Enter`)",
+            "error: in func `my_func`: the type of the `with` value of `a` can't be inferred, so \
+            the `let` needs one",
+            "error: in func `my_func`: `Lock` can't be used with `with`, as it doesn't implement \
+            interface `context` (missing method `Exit`)",
+            "error: in func `my_func`: the type of the `with` value of `d` can't be inferred, so \
+            the `let` needs one",
+            "error: in func `my_func`: `with` can only be used as a statement or as the value of a \
+            `let`",
+            "error: in func `my_func`: `Lock` can't be used with `with`, as it doesn't implement \
+            interface `context` (missing method `Exit`)",
+        ]
+    );
+}
+
+#[test]
+fn check_comprehensions() {
+    let src = r"
+func unknown() -> 1
+
+func my_func(n: Int, xs: list[Int], m: map[str, Int]) -> Int:
+    let a = [x <- for x in n]
+    let b = [unknown() <- for x in xs]
+    let c: list[str] = [x <- for x in xs]
+    let d: map[str, Int] = {k: m[k] <- for k in m}
+    let e: set[str, Int] = {|x <- for x in xs|}
+    0
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `my_func`: only lists, maps, sets and iterators can be iterated over \
+            by a comprehension, but the type of its source isn't one of them (or can't be inferred)",
+            "error: in func `my_func`: the type of the elements of the comprehension can't be \
+            inferred",
+            "error: in func `my_func`: type `set` takes 1 type argument(s), but 2 were given",
+        ]
+    );
+}
+
+#[test]
+fn check_coroutines() {
+    let src = r"
+func numbers() -> coroutine[Int, ()]:
+    yield 1
+    yield
+end
+
+func not_coroutine() -> Int:
+    yield 1
+end
+
+func pairs(n: Int) -> coroutine[Int]:
+    yield n
+end
+
+func main():
+    let a = [x <- for x in numbers()]
+    print(a)
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `numbers`: `yield` needs a value, as the coroutine yields `Int`",
+            "error: in func `not_coroutine`: `yield` can only be used in a func returning a \
+            `coroutine`",
+            "error: in func `pairs`: type `coroutine` takes 2 type argument(s), but 1 were given",
+            "error: in func `pairs`: `yield` can only be used in a func returning a `coroutine`",
+        ]
+    );
+}
+
+#[test]
+fn check_async() {
+    let src = r"
+async func fetch(n: Int) -> Int:
+    n * 2
+end
+
+async func double(n: Int) -> n * 2
+
+async func total() -> Int:
+    let a: Int = await fetch(1)
+    let b = await a
+    a
+end
+
+func main():
+    let c = await fetch(1)
+    print(c)
+end
+
+func peek(f: Future[Int], g: Future) -> Int:
+    let h = Future(1)
+    f.value
+end
+";
+    assert_eq!(
+        check(src),
+        vec![
+            "error: in func `double`: an `async` func must have a block body",
+            "error: in func `total`: only a `Future` can be awaited, not `Int`",
+            "error: in func `main`: `await` can only be used in an `async` func",
+            "error: in func `peek`: struct `Future` needs 1 type argument(s)",
+            "error: in func `peek`: struct `Future` belongs to the runtime, so it can't be constructed",
+            "error: in func `peek`: field `value` of struct `Future` belongs to the runtime, so it \
+            can't be used",
+        ]
+    );
+}
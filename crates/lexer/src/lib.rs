@@ -107,18 +107,33 @@ pub enum TokenType {
     NumberLit,
     // true if it has escaped chars
     StringLit(bool),
+    // The pieces of a string with expressions in it ('"a {x} b {y} c"'), which come before
+    // ('"a {'), between ('} b {') and after ('} c"') them. true if it has escaped chars
+    StringStart(bool),
+    StringMiddle(bool),
+    StringEnd(bool),
+    // '``"abc"``' - nothing in it is escaped
+    RawStringLit,
     // true if it has escaped chars
     CharLit(bool),
 
     // Various symbols
     LeftParen,
     RightParen,
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    // '{|' and '|}'
+    LeftBracePipe,
+    PipeRightBrace,
     Semi,
     Colon,
     Assign,
     Dot,
     Comma,
     RArrow,
+    LArrow,
 
     // Math operators
     Multiply,
@@ -126,6 +141,10 @@ pub enum TokenType {
     Plus,
     Minus,
 
+    // Comparison operators
+    Equal,
+    NotEqual,
+
     // Keywords
     Or,
     And,
@@ -137,6 +156,25 @@ pub enum TokenType {
     If,
     Then,
     Else,
+    Match,
+    With,
+    As,
+    Struct,
+    Enum,
+    Impl,
+    Interface,
+    Type,
+    For,
+    SelfRef,
+    Let,
+    Var,
+    Const,
+    Try,
+    Catch,
+    In,
+    Yield,
+    Async,
+    Await,
 
     Error(TokenErrorKind),
 }
@@ -155,6 +193,8 @@ pub enum StringErrorKind {
     InvalidEscape,
     InvalidHexEscape,
     InvalidUnicodeEscape,
+    // A raw string that isn't quoted inside its backticks
+    InvalidRawQuotes,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -177,6 +217,9 @@ pub struct Lexer<'input> {
     curr_char: Option<(usize, char)>,
     last_token: Option<TokenType>,
     keywords: HashMap<&'static str, TokenType>,
+    // How many braces are open in each expression in a string we are in (innermost last), so we
+    // know which '}' goes back to the string
+    interpolations: Vec<u32>,
 }
 
 impl<'input> Lexer<'input> {
@@ -194,8 +237,34 @@ impl<'input> Lexer<'input> {
     const THEN: &'static str = "then";
     const ELSE: &'static str = "else";
 
+    const MATCH: &'static str = "match";
+    const WITH: &'static str = "with";
+    const AS: &'static str = "as";
+
+    const STRUCT: &'static str = "struct";
+    const ENUM: &'static str = "enum";
+    const IMPL: &'static str = "impl";
+    const INTERFACE: &'static str = "interface";
+    const TYPE: &'static str = "type";
+    const FOR: &'static str = "for";
+    const SELF: &'static str = "self";
+
+    const LET: &'static str = "let";
+    const VAR: &'static str = "var";
+    const CONST: &'static str = "const";
+
+    const TRY: &'static str = "try";
+    const CATCH: &'static str = "catch";
+
+    const IN: &'static str = "in";
+
+    const YIELD: &'static str = "yield";
+
+    const ASYNC: &'static str = "async";
+    const AWAIT: &'static str = "await";
+
     pub fn new(input: &'input str, incl_comments: bool, gen_input_markers: bool) -> Self {
-        let mut keywords = HashMap::with_capacity(7);
+        let mut keywords = HashMap::with_capacity(29);
         keywords.insert(Self::TRUE, TokenType::True);
         keywords.insert(Self::FALSE, TokenType::False);
         keywords.insert(Self::OR, TokenType::Or);
@@ -206,6 +275,25 @@ impl<'input> Lexer<'input> {
         keywords.insert(Self::IF, TokenType::If);
         keywords.insert(Self::THEN, TokenType::Then);
         keywords.insert(Self::ELSE, TokenType::Else);
+        keywords.insert(Self::MATCH, TokenType::Match);
+        keywords.insert(Self::WITH, TokenType::With);
+        keywords.insert(Self::AS, TokenType::As);
+        keywords.insert(Self::STRUCT, TokenType::Struct);
+        keywords.insert(Self::ENUM, TokenType::Enum);
+        keywords.insert(Self::IMPL, TokenType::Impl);
+        keywords.insert(Self::INTERFACE, TokenType::Interface);
+        keywords.insert(Self::TYPE, TokenType::Type);
+        keywords.insert(Self::FOR, TokenType::For);
+        keywords.insert(Self::SELF, TokenType::SelfRef);
+        keywords.insert(Self::LET, TokenType::Let);
+        keywords.insert(Self::VAR, TokenType::Var);
+        keywords.insert(Self::CONST, TokenType::Const);
+        keywords.insert(Self::TRY, TokenType::Try);
+        keywords.insert(Self::CATCH, TokenType::Catch);
+        keywords.insert(Self::IN, TokenType::In);
+        keywords.insert(Self::YIELD, TokenType::Yield);
+        keywords.insert(Self::ASYNC, TokenType::Async);
+        keywords.insert(Self::AWAIT, TokenType::Await);
 
         Self {
             incl_comments,
@@ -215,6 +303,7 @@ impl<'input> Lexer<'input> {
             curr_char: None,
             last_token: None,
             keywords,
+            interpolations: Vec::new(),
         }
     }
 
@@ -270,20 +359,17 @@ impl<'input> Lexer<'input> {
     fn scan_number(&mut self, start_idx: usize) -> Option<LalrpopToken> {
         let mut len = 1;
 
-        loop {
-            match self.char_iter.next() {
-                Some((idx, char)) => match char {
-                    // ASCII digits - always length of 1
-                    '0'..='9' => len += 1,
-                    // If not a valid digit then don't consume and we are done
-                    _ => {
-                        // Save this since not processed yet
-                        self.curr_char = Some((idx, char));
-                        break;
-                    }
-                },
-                // EOI
-                None => break,
+        // Stops at EOI
+        for (idx, char) in self.char_iter.by_ref() {
+            match char {
+                // ASCII digits - always length of 1
+                '0'..='9' => len += 1,
+                // If not a valid digit then don't consume and we are done
+                _ => {
+                    // Save this since not processed yet
+                    self.curr_char = Some((idx, char));
+                    break;
+                }
             }
         }
 
@@ -298,7 +384,6 @@ impl<'input> Lexer<'input> {
     ) -> (usize, Option<TokenType>) {
         // The escape char + 'u' or 'x' char
         let mut len = 2;
-
         loop {
             match self.char_iter.next() {
                 Some((_, char)) => match char {
@@ -328,6 +413,32 @@ impl<'input> Lexer<'input> {
         (len, None)
     }
 
+    // Octal and binary escapes take as many digits as there are (up to the max), so unlike hex
+    // and unicode escapes, the char after them isn't consumed
+    fn scan_radix_escape(
+        &mut self,
+        invalid_error_kind: TokenErrorKind,
+        radix: u32,
+        max_digits: usize,
+    ) -> (usize, Option<TokenType>) {
+        let mut digits = 0;
+
+        while digits < max_digits
+            && matches!(self.char_iter.clone().next(), Some((_, char)) if char.is_digit(radix))
+        {
+            self.char_iter.next();
+            digits += 1;
+        }
+
+        // The escape char + 'o' or 'b' char, then the digits - ASCII, so always length of 1
+        let len = digits + 2;
+        if digits == 0 {
+            (len, Some(TokenType::Error(invalid_error_kind)))
+        } else {
+            (len, None)
+        }
+    }
+
     fn scan_escape(
         &mut self,
         quote: char,
@@ -350,8 +461,13 @@ impl<'input> Lexer<'input> {
                     unterm_error_kind,
                     8,
                 ),
+                // Octal and binary escapes (chars only)
+                'o' if quote == '\'' => self.scan_radix_escape(invalid_error_kind, 8, 3),
+                'b' if quote == '\'' => self.scan_radix_escape(invalid_error_kind, 2, 8),
                 // Escaped quote
                 _ if char == quote => (2, None),
+                // Escaped braces, which otherwise start and end expressions in strings
+                '{' | '}' if quote == '"' => (2, None),
                 // Invalid escape
                 _ => (
                     char.len_utf8() + 1,
@@ -363,10 +479,48 @@ impl<'input> Lexer<'input> {
         }
     }
 
-    fn scan_string(&mut self, start_idx: usize) -> Option<LalrpopToken> {
-        // The opening quote
+    // Ends at the first double backtick, which the closing quote comes right before
+    fn scan_raw_string(&mut self, start_idx: usize) -> Option<LalrpopToken> {
+        // After the opening backticks
+        let rest = &self.input[start_idx + 2..];
+
+        let (len, token) = match rest.find("``") {
+            Some(end) => {
+                let quoted = &rest[..end];
+                let token = if quoted.len() >= 2 && quoted.starts_with('"') && quoted.ends_with('"')
+                {
+                    TokenType::RawStringLit
+                } else {
+                    TokenType::Error(TokenErrorKind::InvalidString(
+                        StringErrorKind::InvalidRawQuotes,
+                    ))
+                };
+                // Both pairs of backticks - always length of 1 each
+                (end + 4, token)
+            }
+            // Unexpected EOI
+            None => (
+                rest.len() + 2,
+                TokenType::Error(TokenErrorKind::InvalidString(StringErrorKind::Unterminated)),
+            ),
+        };
+
+        // Consume it, up to and including its last char
+        let last_idx = start_idx + len - 1;
+        self.char_iter
+            .by_ref()
+            .take_while(|(idx, _)| *idx < last_idx)
+            .for_each(drop);
+
+        self.emit_token(token, start_idx, len)
+    }
+
+    // 'resumed' is set when scanning the rest of a string after an expression in it
+    fn scan_string(&mut self, start_idx: usize, resumed: bool) -> Option<LalrpopToken> {
+        // The opening quote (or the brace closing the expression)
         let mut len = 1;
-        let mut token = TokenType::StringLit(false);
+        let mut has_escapes = false;
+        let mut error = None;
 
         loop {
             match self.char_iter.next() {
@@ -383,18 +537,34 @@ impl<'input> Lexer<'input> {
                         len += esc_len;
 
                         // Did we have a token error?
-                        if let Some(token_err) = token_err {
-                            token = token_err;
-                        // We have seen an escaped char, but only set if not error
-                        } else if let TokenType::StringLit(false) = token {
-                            token = TokenType::StringLit(true);
+                        if token_err.is_some() {
+                            error = token_err;
+                        } else {
+                            has_escapes = true;
                         }
                     }
                     // Closing quote
                     '"' => {
                         // ASCII quote - always length of 1
                         len += 1;
-                        break;
+
+                        let token = match resumed {
+                            true => TokenType::StringEnd(has_escapes),
+                            false => TokenType::StringLit(has_escapes),
+                        };
+                        return self.emit_token(error.unwrap_or(token), start_idx, len);
+                    }
+                    // Start of an expression, whose tokens come next
+                    '{' => {
+                        // ASCII brace - always length of 1
+                        len += 1;
+                        self.interpolations.push(0);
+
+                        let token = match resumed {
+                            true => TokenType::StringMiddle(has_escapes),
+                            false => TokenType::StringStart(has_escapes),
+                        };
+                        return self.emit_token(error.unwrap_or(token), start_idx, len);
                     }
                     // Newline and carriage return are not allowed in strings (must be escaped)
                     // TODO: Alternative, allow '\n' and strip '\r' later?
@@ -408,9 +578,9 @@ impl<'input> Lexer<'input> {
                         len += 1;
 
                         // We never saw closing quote
-                        token = TokenType::Error(TokenErrorKind::InvalidString(
+                        error = Some(TokenType::Error(TokenErrorKind::InvalidString(
                             StringErrorKind::InvalidChar,
-                        ));
+                        )));
                     }
                     // Valid char, add to string. Could be unicode, so we don't know the length
                     _ => len += char.len_utf8(),
@@ -418,15 +588,13 @@ impl<'input> Lexer<'input> {
                 // Unexpected EOI
                 None => {
                     // We never saw closing quote
-                    token = TokenType::Error(TokenErrorKind::InvalidString(
+                    let token = TokenType::Error(TokenErrorKind::InvalidString(
                         StringErrorKind::Unterminated,
                     ));
-                    break;
+                    return self.emit_token(token, start_idx, len);
                 }
             }
         }
-
-        self.emit_token(token, start_idx, len)
     }
 
     fn scan_char(&mut self, start_idx: usize) -> Option<LalrpopToken> {
@@ -571,8 +739,20 @@ impl<'input> Iterator for Lexer<'input> {
                                 Some(
                                     TokenType::Ident
                                     | TokenType::NumberLit
+                                    | TokenType::StringLit(_)
+                                    | TokenType::StringEnd(_)
+                                    | TokenType::RawStringLit
+                                    | TokenType::CharLit(_)
+                                    | TokenType::True
+                                    | TokenType::False
+                                    | TokenType::SelfRef
                                     | TokenType::RightParen
-                                    | TokenType::End,
+                                    | TokenType::RightBracket
+                                    | TokenType::RightBrace
+                                    | TokenType::PipeRightBrace
+                                    | TokenType::End
+                                    // It may or may not be followed by a value
+                                    | TokenType::Yield,
                                 ) => {
                                     // Semicolon actual token can be ';' OR '\n'
                                     self.emit_token(TokenType::Semi, idx, 1)
@@ -582,12 +762,96 @@ impl<'input> Iterator for Lexer<'input> {
                             }
                         }
                         ':' => self.emit_token(TokenType::Colon, idx, 1),
-                        '=' => self.emit_token(TokenType::Assign, idx, 1),
+                        // Handle assignment and equality disambiguation
+                        '=' => match self.char_iter.next() {
+                            Some((_, '=')) => self.emit_token(TokenType::Equal, idx, 2),
+                            Some((next_idx, char)) => {
+                                // Save this since not processed yet
+                                self.curr_char = Some((next_idx, char));
+                                self.emit_token(TokenType::Assign, idx, 1)
+                            }
+                            // EOI
+                            None => self.emit_token(TokenType::Assign, idx, 1),
+                        },
+                        // Only valid as part of '!=' ('not' is used for negation)
+                        '!' => match self.char_iter.next() {
+                            Some((_, '=')) => self.emit_token(TokenType::NotEqual, idx, 2),
+                            Some((next_idx, char)) => {
+                                // Save this since not processed yet
+                                self.curr_char = Some((next_idx, char));
+                                self.emit_token(
+                                    TokenType::Error(TokenErrorKind::UnknownType),
+                                    idx,
+                                    1,
+                                )
+                            }
+                            // EOI
+                            None => self.emit_token(
+                                TokenType::Error(TokenErrorKind::UnknownType),
+                                idx,
+                                1,
+                            ),
+                        },
                         '.' => self.emit_token(TokenType::Dot, idx, 1),
                         ',' => self.emit_token(TokenType::Comma, idx, 1),
                         ';' => self.emit_token(TokenType::Semi, idx, 1),
                         '(' => self.emit_token(TokenType::LeftParen, idx, 1),
                         ')' => self.emit_token(TokenType::RightParen, idx, 1),
+                        '[' => self.emit_token(TokenType::LeftBracket, idx, 1),
+                        ']' => self.emit_token(TokenType::RightBracket, idx, 1),
+                        // Handle brace and set brace disambiguation
+                        '{' => {
+                            if let Some(open) = self.interpolations.last_mut() {
+                                *open += 1;
+                            }
+
+                            match self.char_iter.next() {
+                                Some((_, '|')) => self.emit_token(TokenType::LeftBracePipe, idx, 2),
+                                Some((next_idx, char)) => {
+                                    // Save this since not processed yet
+                                    self.curr_char = Some((next_idx, char));
+                                    self.emit_token(TokenType::LeftBrace, idx, 1)
+                                }
+                                // EOI
+                                None => self.emit_token(TokenType::LeftBrace, idx, 1),
+                            }
+                        }
+                        // Ends the expression in a string if it has no braces of its own open
+                        '}' => match self.interpolations.last_mut() {
+                            Some(0) => {
+                                self.interpolations.pop();
+                                self.scan_string(idx, true)
+                            }
+                            Some(open) => {
+                                *open -= 1;
+                                self.emit_token(TokenType::RightBrace, idx, 1)
+                            }
+                            None => self.emit_token(TokenType::RightBrace, idx, 1),
+                        },
+                        // Only valid as part of '|}'
+                        '|' => match self.char_iter.next() {
+                            Some((_, '}')) => {
+                                if let Some(open) = self.interpolations.last_mut() {
+                                    *open = open.saturating_sub(1);
+                                }
+                                self.emit_token(TokenType::PipeRightBrace, idx, 2)
+                            }
+                            Some((next_idx, char)) => {
+                                // Save this since not processed yet
+                                self.curr_char = Some((next_idx, char));
+                                self.emit_token(
+                                    TokenType::Error(TokenErrorKind::UnknownType),
+                                    idx,
+                                    1,
+                                )
+                            }
+                            // EOI
+                            None => self.emit_token(
+                                TokenType::Error(TokenErrorKind::UnknownType),
+                                idx,
+                                1,
+                            ),
+                        },
                         '*' => self.emit_token(TokenType::Multiply, idx, 1),
                         '/' => self.emit_token(TokenType::Divide, idx, 1),
                         '+' => self.emit_token(TokenType::Plus, idx, 1),
@@ -602,10 +866,50 @@ impl<'input> Iterator for Lexer<'input> {
                             // EOI
                             None => self.emit_token(TokenType::Minus, idx, 1),
                         },
-                        '"' => self.scan_string(idx),
+                        // Only valid as part of '<-' (there are no comparison operators yet)
+                        '<' => match self.char_iter.next() {
+                            Some((_, '-')) => self.emit_token(TokenType::LArrow, idx, 2),
+                            Some((next_idx, char)) => {
+                                // Save this since not processed yet
+                                self.curr_char = Some((next_idx, char));
+                                self.emit_token(
+                                    TokenType::Error(TokenErrorKind::UnknownType),
+                                    idx,
+                                    1,
+                                )
+                            }
+                            // EOI
+                            None => self.emit_token(
+                                TokenType::Error(TokenErrorKind::UnknownType),
+                                idx,
+                                1,
+                            ),
+                        },
+                        '"' => self.scan_string(idx, false),
+                        // Only valid as the start of a raw string
+                        '`' => match self.char_iter.next() {
+                            Some((_, '`')) => self.scan_raw_string(idx),
+                            Some((next_idx, char)) => {
+                                // Save this since not processed yet
+                                self.curr_char = Some((next_idx, char));
+                                self.emit_token(
+                                    TokenType::Error(TokenErrorKind::UnknownType),
+                                    idx,
+                                    1,
+                                )
+                            }
+                            // EOI
+                            None => self.emit_token(
+                                TokenType::Error(TokenErrorKind::UnknownType),
+                                idx,
+                                1,
+                            ),
+                        },
                         '\'' => self.scan_char(idx),
                         // Start of integer literal
                         '1'..='9' => self.scan_number(idx),
+                        // Zero is always on its own (no leading zeros)
+                        '0' => self.emit_token(TokenType::NumberLit, idx, 1),
                         // Next two are start of keyword or identifier
                         '_' => self.scan_ident_or_keyword(idx, 1),
                         // NOTE: This is last because if not ASCII, it might be slow determining if unicode alpha
@@ -667,6 +971,65 @@ mod test {
         );
     }
 
+    #[test]
+    fn string_raw() {
+        lexer_single_token_test(r#"``"a\b"c{"``"#, TokenType::RawStringLit, 0, 12);
+        // Newlines are part of it, rather than ending the line
+        let mut lexer = Lexer::new("``\"a\nb\"``\nx", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::RawStringLit, 9))));
+        assert_eq!(lexer.next(), Some(Ok((9, TokenType::Semi, 10))));
+        assert_eq!(lexer.next(), Some(Ok((10, TokenType::Ident, 11))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn string_raw_invalid() {
+        let tt = TokenType::Error(TokenErrorKind::InvalidString(StringErrorKind::Unterminated));
+        lexer_single_token_test(r#"``"abc"`"#, tt, 0, 8);
+        let tt = TokenType::Error(TokenErrorKind::InvalidString(
+            StringErrorKind::InvalidRawQuotes,
+        ));
+        lexer_single_token_test("``abc``", tt, 0, 7);
+        lexer_single_token_test(r#"``"``"#, tt, 0, 5);
+    }
+
+    #[test]
+    fn string_interpolated() {
+        let mut lexer = Lexer::new(r#""a\{ {x} b {m["k"]} c""#, false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::StringStart(true), 6))));
+        assert_eq!(lexer.next(), Some(Ok((6, TokenType::Ident, 7))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((7, TokenType::StringMiddle(false), 12)))
+        );
+        assert_eq!(lexer.next(), Some(Ok((12, TokenType::Ident, 13))));
+        assert_eq!(lexer.next(), Some(Ok((13, TokenType::LeftBracket, 14))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((14, TokenType::StringLit(false), 17)))
+        );
+        assert_eq!(lexer.next(), Some(Ok((17, TokenType::RightBracket, 18))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((18, TokenType::StringEnd(false), 22)))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn string_interpolated_braces() {
+        let mut lexer = Lexer::new(r#""{ {|x|} }""#, false, false);
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((0, TokenType::StringStart(false), 2)))
+        );
+        assert_eq!(lexer.next(), Some(Ok((3, TokenType::LeftBracePipe, 5))));
+        assert_eq!(lexer.next(), Some(Ok((5, TokenType::Ident, 6))));
+        assert_eq!(lexer.next(), Some(Ok((6, TokenType::PipeRightBrace, 8))));
+        assert_eq!(lexer.next(), Some(Ok((9, TokenType::StringEnd(false), 11))));
+        assert_eq!(lexer.next(), None);
+    }
+
     #[test]
     fn string_unterminated() {
         let tt = TokenType::Error(TokenErrorKind::InvalidString(StringErrorKind::Unterminated));
@@ -701,6 +1064,8 @@ mod test {
         let tt = |kind| TokenType::Error(TokenErrorKind::InvalidString(kind));
         // In simple escape
         lexer_single_token_test(r#""\|""#, tt(crate::StringErrorKind::InvalidEscape), 0, 4);
+        // Octal escapes are only for chars
+        lexer_single_token_test(r#""\o7""#, tt(crate::StringErrorKind::InvalidEscape), 0, 5);
 
         // In unicode escape
         lexer_single_token_test(
@@ -736,6 +1101,8 @@ mod test {
         lexer_single_token_test(r#"'\t'"#, TokenType::CharLit(true), 0, 4);
         lexer_single_token_test(r#"'\u019aEf'"#, TokenType::CharLit(true), 0, 10);
         lexer_single_token_test(r#"'\x0F'"#, TokenType::CharLit(true), 0, 6);
+        lexer_single_token_test(r#"'\o77'"#, TokenType::CharLit(true), 0, 6);
+        lexer_single_token_test(r#"'\b01010101'"#, TokenType::CharLit(true), 0, 12);
     }
 
     #[test]
@@ -787,6 +1154,10 @@ mod test {
 
         // In hex escape
         lexer_single_token_test(r#"'\x|'"#, tt(crate::CharErrorKind::InvalidHexEscape), 0, 5);
+
+        // Octal and binary escapes need digits
+        lexer_single_token_test(r#"'\o'"#, tt(crate::CharErrorKind::InvalidEscape), 0, 4);
+        lexer_single_token_test(r#"'\b2'"#, tt(crate::CharErrorKind::CharTooLong), 0, 5);
     }
 
     #[test]
@@ -818,6 +1189,139 @@ mod test {
         lexer_single_token_test("not", TokenType::Not, 0, 3);
     }
 
+    // *** Match Tests ***
+
+    #[test]
+    fn match_keywords() {
+        lexer_single_token_test("match", TokenType::Match, 0, 5);
+        lexer_single_token_test("with", TokenType::With, 0, 4);
+        lexer_single_token_test("as", TokenType::As, 0, 2);
+    }
+
+    #[test]
+    fn operators() {
+        let mut lexer = Lexer::new("a == b != c = d[1]\n!", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::Ident, 1))));
+        assert_eq!(lexer.next(), Some(Ok((2, TokenType::Equal, 4))));
+        assert_eq!(lexer.next(), Some(Ok((5, TokenType::Ident, 6))));
+        assert_eq!(lexer.next(), Some(Ok((7, TokenType::NotEqual, 9))));
+        assert_eq!(lexer.next(), Some(Ok((10, TokenType::Ident, 11))));
+        assert_eq!(lexer.next(), Some(Ok((12, TokenType::Assign, 13))));
+        assert_eq!(lexer.next(), Some(Ok((14, TokenType::Ident, 15))));
+        assert_eq!(lexer.next(), Some(Ok((15, TokenType::LeftBracket, 16))));
+        assert_eq!(lexer.next(), Some(Ok((16, TokenType::NumberLit, 17))));
+        assert_eq!(lexer.next(), Some(Ok((17, TokenType::RightBracket, 18))));
+        // Special semi due to line ending in right bracket
+        assert_eq!(lexer.next(), Some(Ok((18, TokenType::Semi, 19))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((19, TokenType::Error(TokenErrorKind::UnknownType), 20)))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn left_arrow() {
+        let mut lexer = Lexer::new("x <- y < z", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::Ident, 1))));
+        assert_eq!(lexer.next(), Some(Ok((2, TokenType::LArrow, 4))));
+        assert_eq!(lexer.next(), Some(Ok((5, TokenType::Ident, 6))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((7, TokenType::Error(TokenErrorKind::UnknownType), 8)))
+        );
+        assert_eq!(lexer.next(), Some(Ok((9, TokenType::Ident, 10))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn braces() {
+        let mut lexer = Lexer::new("{|x|} {y}|", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::LeftBracePipe, 2))));
+        assert_eq!(lexer.next(), Some(Ok((2, TokenType::Ident, 3))));
+        assert_eq!(lexer.next(), Some(Ok((3, TokenType::PipeRightBrace, 5))));
+        assert_eq!(lexer.next(), Some(Ok((6, TokenType::LeftBrace, 7))));
+        assert_eq!(lexer.next(), Some(Ok((7, TokenType::Ident, 8))));
+        assert_eq!(lexer.next(), Some(Ok((8, TokenType::RightBrace, 9))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok((9, TokenType::Error(TokenErrorKind::UnknownType), 10)))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn zero() {
+        lexer_single_token_test("0", TokenType::NumberLit, 0, 1);
+
+        let mut lexer = Lexer::new("01", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::NumberLit, 1))));
+        assert_eq!(lexer.next(), Some(Ok((1, TokenType::NumberLit, 2))));
+    }
+
+    #[test]
+    fn semi_after_literal() {
+        let mut lexer = Lexer::new("1, 2 -> true\n_ -> 'a'\n", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::NumberLit, 1))));
+        assert_eq!(lexer.next(), Some(Ok((1, TokenType::Comma, 2))));
+        assert_eq!(lexer.next(), Some(Ok((3, TokenType::NumberLit, 4))));
+        assert_eq!(lexer.next(), Some(Ok((5, TokenType::RArrow, 7))));
+        assert_eq!(lexer.next(), Some(Ok((8, TokenType::True, 12))));
+        // Special semi due to line ending in bool literal
+        assert_eq!(lexer.next(), Some(Ok((12, TokenType::Semi, 13))));
+        assert_eq!(lexer.next(), Some(Ok((13, TokenType::Ident, 14))));
+        assert_eq!(lexer.next(), Some(Ok((15, TokenType::RArrow, 17))));
+        assert_eq!(lexer.next(), Some(Ok((18, TokenType::CharLit(false), 21))));
+        // Special semi due to line ending in char literal
+        assert_eq!(lexer.next(), Some(Ok((21, TokenType::Semi, 22))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    // *** Struct Tests ***
+
+    #[test]
+    fn struct_keywords() {
+        lexer_single_token_test("struct", TokenType::Struct, 0, 6);
+        lexer_single_token_test("enum", TokenType::Enum, 0, 4);
+        lexer_single_token_test("impl", TokenType::Impl, 0, 4);
+        lexer_single_token_test("interface", TokenType::Interface, 0, 9);
+        lexer_single_token_test("type", TokenType::Type, 0, 4);
+        lexer_single_token_test("for", TokenType::For, 0, 3);
+        lexer_single_token_test("self", TokenType::SelfRef, 0, 4);
+        lexer_single_token_test("let", TokenType::Let, 0, 3);
+        lexer_single_token_test("var", TokenType::Var, 0, 3);
+        lexer_single_token_test("const", TokenType::Const, 0, 5);
+    }
+
+    // *** Try Tests ***
+
+    #[test]
+    fn try_keywords() {
+        lexer_single_token_test("try", TokenType::Try, 0, 3);
+        lexer_single_token_test("catch", TokenType::Catch, 0, 5);
+    }
+
+    #[test]
+    fn in_keyword() {
+        lexer_single_token_test("in", TokenType::In, 0, 2);
+    }
+
+    #[test]
+    fn async_await_keywords() {
+        lexer_single_token_test("async", TokenType::Async, 0, 5);
+        lexer_single_token_test("await", TokenType::Await, 0, 5);
+    }
+
+    #[test]
+    fn yield_semi() {
+        let mut lexer = Lexer::new("yield\nx", false, false);
+        assert_eq!(lexer.next(), Some(Ok((0, TokenType::Yield, 5))));
+        // Special semi due to line ending in yield
+        assert_eq!(lexer.next(), Some(Ok((5, TokenType::Semi, 6))));
+        assert_eq!(lexer.next(), Some(Ok((6, TokenType::Ident, 7))));
+        assert_eq!(lexer.next(), None);
+    }
+
     // *** Full Lexer Tests ***
 
     #[test]
@@ -906,6 +1410,8 @@ mod test {
             lexer.next(),
             Some(Ok((154, TokenType::StringLit(true), 175)))
         );
+        // Special semi due to line ending in string literal
+        assert_eq!(lexer.next(), Some(Ok((175, TokenType::Semi, 176))));
 
         if gen_input_markers {
             assert_eq!(lexer.next(), Some(Ok((0, TokenType::EndOfInput, 0))));
@@ -1,5 +1,5 @@
 module github.com/nu11ptr/interop
 
-go 1.14
+go 1.18
 
 require github.com/antlr/antlr4 v0.0.0-20200119161855-7a3f40bc341d
@@ -0,0 +1,8 @@
+package rt
+
+// Context is a type that can be used with 'with': Enter is called before its block, and Exit after
+// it however it is left.
+type Context interface {
+	Enter()
+	Exit()
+}
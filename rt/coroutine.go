@@ -0,0 +1,80 @@
+package rt
+
+import "runtime"
+
+// Coroutine runs a function on its own goroutine, which hands each value it yields to Resume and
+// then waits to be resumed again, or to be closed
+//
+// TODO: lower coroutines to state machines instead, so they don't need a goroutine each
+type Coroutine[Out, In any] struct {
+	resume chan In
+	yield  chan Out
+	stop   chan struct{}
+	done   bool
+	closed bool
+}
+
+func NewCoroutine[Out, In any](body func(yield func(Out) In)) *Coroutine[Out, In] {
+	c := &Coroutine[Out, In]{
+		resume: make(chan In),
+		yield:  make(chan Out),
+		stop:   make(chan struct{}),
+	}
+
+	go func() {
+		// It doesn't start until it is first resumed
+		c.wait()
+		body(func(out Out) In {
+			select {
+			case c.yield <- out:
+			case <-c.stop:
+				runtime.Goexit()
+			}
+			return c.wait()
+		})
+		close(c.yield)
+	}()
+
+	return c
+}
+
+// wait blocks the goroutine until the coroutine is resumed, and ends it if it is closed instead
+func (c *Coroutine[Out, In]) wait() In {
+	select {
+	case in := <-c.resume:
+		return in
+	case <-c.stop:
+		runtime.Goexit()
+		panic("unreachable")
+	}
+}
+
+// Resume runs the coroutine until its next yield, which returns 'in' (unless it is the first).
+// False once the coroutine has finished or been closed
+func (c *Coroutine[Out, In]) Resume(in In) (Out, bool) {
+	if c.done {
+		var zero Out
+		return zero, false
+	}
+
+	c.resume <- in
+	out, ok := <-c.yield
+	c.done = !ok
+	return out, ok
+}
+
+// Next makes a coroutine an Iter, resuming it with the zero value
+func (c *Coroutine[Out, In]) Next() (Out, bool) {
+	var in In
+	return c.Resume(in)
+}
+
+// Close ends the goroutine of a coroutine that won't be resumed again, so it doesn't leak
+func (c *Coroutine[Out, In]) Close() {
+	if c.closed {
+		return
+	}
+	c.closed = true
+	c.done = true
+	close(c.stop)
+}
@@ -0,0 +1,25 @@
+package rt
+
+// Future is what calling an 'async' func returns: the func runs on its own goroutine, and Await
+// waits for the value it returns
+type Future[T any] struct {
+	done  chan struct{}
+	value *T
+}
+
+func Async[T any](body func() T) Future[T] {
+	f := Future[T]{done: make(chan struct{}), value: new(T)}
+
+	go func() {
+		*f.value = body()
+		// Every Await (however many there are) is released once it is closed
+		close(f.done)
+	}()
+
+	return f
+}
+
+func (f Future[T]) Await() T {
+	<-f.done
+	return *f.value
+}
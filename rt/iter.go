@@ -0,0 +1,48 @@
+package rt
+
+// Iter is a lazy sequence of values: Next returns the next one, or false once there are no more.
+type Iter[T any] interface {
+	Next() (T, bool)
+}
+
+// IterFunc is an Iter whose Next is the function, which keeps the state of the iteration.
+type IterFunc[T any] func() (T, bool)
+
+func (next IterFunc[T]) Next() (T, bool) {
+	return next()
+}
+
+// Done is what Next returns once there are no more values.
+func Done[T any]() (T, bool) {
+	var zero T
+	return zero, false
+}
+
+// Close lets go of an Iter that may not have been run to the end, closing it if it is a
+// Coroutine (or something else that has to be closed).
+func Close(it any) {
+	if closer, ok := it.(interface{ Close() }); ok {
+		closer.Close()
+	}
+}
+
+// Elements iterates over the elements of a slice.
+func Elements[T any](items []T) Iter[T] {
+	idx := 0
+	return IterFunc[T](func() (T, bool) {
+		if idx == len(items) {
+			return Done[T]()
+		}
+		idx++
+		return items[idx-1], true
+	})
+}
+
+// Keys iterates over the keys of a map (or the elements of a set), in no particular order.
+func Keys[K comparable, V any](m map[K]V) Iter[K] {
+	keys := make([]K, 0, len(m))
+	for key := range m {
+		keys = append(keys, key)
+	}
+	return Elements(keys)
+}
@@ -0,0 +1,54 @@
+// Package rt is the runtime of Go code generated by the Interop compiler. It implements the
+// Interop prelude (crates/compiler/src/prelude.int), with the method names exported.
+package rt
+
+// Option is a value that may not be there: either Some value or None.
+type Option[T any] interface {
+	isOption(T)
+	IsSome() bool
+	IsNone() bool
+	UnwrapOr(defaultVal T) T
+}
+
+type Some[T any] struct {
+	F0 T
+}
+
+func (Some[T]) isOption(T) {}
+
+type None[T any] struct{}
+
+func (None[T]) isOption(T) {}
+
+func (Some[T]) IsSome() bool {
+	return true
+}
+
+func (None[T]) IsSome() bool {
+	return false
+}
+
+func (Some[T]) IsNone() bool {
+	return false
+}
+
+func (None[T]) IsNone() bool {
+	return true
+}
+
+func (self Some[T]) UnwrapOr(defaultVal T) T {
+	return self.F0
+}
+
+func (None[T]) UnwrapOr(defaultVal T) T {
+	return defaultVal
+}
+
+// MapOption is 'map' of Option: Some of what f returns for the value, or None. It is a func as Go
+// methods can't have type parameters of their own
+func MapOption[T, U any](o Option[T], f func(T) U) Option[U] {
+	if some, ok := o.(Some[T]); ok {
+		return Some[U]{F0: f(some.F0)}
+	}
+	return None[U]{}
+}
@@ -0,0 +1,86 @@
+package rt
+
+// Result is the outcome of something that can fail: either Ok with a value or Err with an error.
+type Result[T any] interface {
+	isResult(T)
+	IsOk() bool
+	IsErr() bool
+	UnwrapOr(defaultVal T) T
+	Ok() Option[T]
+	Err() Option[error]
+	// Get is how 'try' unwraps a Result: the value, or the error if there is one
+	Get() (T, error)
+}
+
+type Ok[T any] struct {
+	F0 T
+}
+
+func (Ok[T]) isResult(T) {}
+
+type Err[T any] struct {
+	F0 error
+}
+
+func (Err[T]) isResult(T) {}
+
+func (Ok[T]) IsOk() bool {
+	return true
+}
+
+func (Err[T]) IsOk() bool {
+	return false
+}
+
+func (Ok[T]) IsErr() bool {
+	return false
+}
+
+func (Err[T]) IsErr() bool {
+	return true
+}
+
+func (self Ok[T]) UnwrapOr(defaultVal T) T {
+	return self.F0
+}
+
+func (Err[T]) UnwrapOr(defaultVal T) T {
+	return defaultVal
+}
+
+func (self Ok[T]) Ok() Option[T] {
+	return Some[T]{F0: self.F0}
+}
+
+func (Err[T]) Ok() Option[T] {
+	return None[T]{}
+}
+
+func (Ok[T]) Err() Option[error] {
+	return None[error]{}
+}
+
+func (self Err[T]) Err() Option[error] {
+	return Some[error]{F0: self.F0}
+}
+
+func (self Ok[T]) Get() (T, error) {
+	return self.F0, nil
+}
+
+func (self Err[T]) Get() (T, error) {
+	var zero T
+	return zero, self.F0
+}
+
+// MapResult is 'map' of Result: Ok of what f returns for the value, or the same error
+func MapResult[T, U any](r Result[T], f func(T) U) Result[U] {
+	switch r := r.(type) {
+	case Ok[T]:
+		return Ok[U]{F0: f(r.F0)}
+	case Err[T]:
+		return Err[U]{F0: r.F0}
+	default:
+		panic("invalid Result value")
+	}
+}